@@ -0,0 +1,213 @@
+//! Command line access to the exports, for drawings saved as JSON
+//!
+//! ```text
+//! track-drawer-cli svg <drawing.json> <out.svg> [--width <px>] [--background <#rrggbb>]
+//! track-drawer-cli png <drawing.json> <out.png> [--width <px>] [--background <#rrggbb>] [--no-anti-alias]
+//! track-drawer-cli pdf <drawing.json> <out.pdf> [--paper <a3|a4|a5|letter>] [--landscape] [--margin <mm>] [--caption <text>]
+//! track-drawer-cli <gcode|hpgl> <drawing.json> <out> [--width <mm>] [--draw-speed <mm/min>] [--travel-speed <mm/min>]
+//!     [--pen-up <gcode>] [--pen-down <gcode>] [--points]
+//! track-drawer-cli dxf <drawing.json> <out.dxf> [--width <mm>] [--points] [--outline]
+//! track-drawer-cli outline <drawing.json> <out.svg>
+//! ```
+
+use std::{env, fs, process};
+
+use track_drawer::drawing::Drawing;
+use track_drawer::export::{dxf, outline, pdf, plotter, png, svg};
+use track_drawer::render::Colour;
+
+const USAGE: &str = "Usage:
+    track-drawer-cli svg <drawing.json> <out.svg> [--width <px>] [--background <#rrggbb>]
+    track-drawer-cli png <drawing.json> <out.png> [--width <px>] [--background <#rrggbb>] [--no-anti-alias]
+    track-drawer-cli pdf <drawing.json> <out.pdf> [--paper <a3|a4|a5|letter>] [--landscape] [--margin <mm>] [--caption <text>]
+    track-drawer-cli <gcode|hpgl> <drawing.json> <out> [--width <mm>] [--draw-speed <mm/min>] [--travel-speed <mm/min>]
+        [--pen-up <gcode>] [--pen-down <gcode>] [--points]
+    track-drawer-cli dxf <drawing.json> <out.dxf> [--width <mm>] [--points] [--outline]
+    track-drawer-cli outline <drawing.json> <out.svg>";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(err) = run(&args) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    match args {
+        [format, input, output, flags @ ..] if format == "svg" => {
+            let drawing = read_drawing(input)?;
+            let options = png_options(flags)?;
+            let out = svg::render(&drawing.scene(), options.width, options.background);
+            write_output(output, out.as_bytes())
+        }
+        [format, input, output, flags @ ..] if format == "png" => {
+            let drawing = read_drawing(input)?;
+            let options = png_options(flags)?;
+            let bytes = png::render(&drawing.scene(), &options)?;
+            write_output(output, &bytes)
+        }
+        [format, input, output, flags @ ..] if format == "pdf" => {
+            let drawing = read_drawing(input)?;
+            let (options, caption) = pdf_options(flags)?;
+            let bytes = pdf::render(&[(drawing.scene(), caption)], &options);
+            write_output(output, &bytes)
+        }
+        [format, input, output, flags @ ..] if format == "gcode" || format == "hpgl" => {
+            let drawing = read_drawing(input)?;
+            let options = plotter_options(flags)?;
+            let plot = plotter::plan(&drawing, &options);
+            eprintln!(
+                "Drawing {:.0} mm, pen up travel {:.0} mm, {} pen lifts, about {:.0} s",
+                plot.stats.draw_mm, plot.stats.travel_mm, plot.stats.pen_lifts, plot.stats.seconds
+            );
+            let out = if format == "gcode" {
+                plotter::gcode(&plot, &options)
+            } else {
+                plotter::hpgl(&plot, &options)
+            };
+            write_output(output, out.as_bytes())
+        }
+        [format, input, output, flags @ ..] if format == "dxf" => {
+            let drawing = read_drawing(input)?;
+            let options = dxf_options(flags)?;
+            write_output(output, dxf::render(&drawing, &options).as_bytes())
+        }
+        [format, input, output] if format == "outline" => {
+            let drawing = read_drawing(input)?;
+            write_output(output, outline::svg(&drawing.scene()).as_bytes())
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn write_output(path: &str, bytes: &[u8]) -> Result<(), String> {
+    fs::write(path, bytes).map_err(|err| format!("Writing {} failed: {}", path, err))
+}
+
+fn read_drawing(path: &str) -> Result<Drawing, String> {
+    let json =
+        fs::read_to_string(path).map_err(|err| format!("Reading {} failed: {}", path, err))?;
+    serde_json::from_str(&json).map_err(|err| format!("{} isn't a valid drawing: {}", path, err))
+}
+
+fn png_options(mut flags: &[String]) -> Result<png::Options, String> {
+    let mut options = png::Options::default();
+    while let Some((flag, rest)) = flags.split_first() {
+        flags = match (flag.as_str(), rest) {
+            ("--width", [width, rest @ ..]) => {
+                options.width = width
+                    .parse()
+                    .map_err(|_| format!("Invalid width: {}", width))?;
+                rest
+            }
+            ("--background", [colour, rest @ ..]) => {
+                options.background =
+                    Some(Colour::from_hex(colour).ok_or(format!("Invalid colour: {}", colour))?);
+                rest
+            }
+            ("--no-anti-alias", rest) => {
+                options.anti_alias = false;
+                rest
+            }
+            _ => return Err(USAGE.to_string()),
+        };
+    }
+    Ok(options)
+}
+
+fn pdf_options(mut flags: &[String]) -> Result<(pdf::Options, Option<String>), String> {
+    let mut options = pdf::Options::default();
+    let mut caption = None;
+    while let Some((flag, rest)) = flags.split_first() {
+        flags = match (flag.as_str(), rest) {
+            ("--paper", [paper, rest @ ..]) => {
+                options.paper = *pdf::PaperSize::ALL
+                    .iter()
+                    .find(|size| size.name().eq_ignore_ascii_case(paper))
+                    .ok_or(format!("Unknown paper size: {}", paper))?;
+                rest
+            }
+            ("--landscape", rest) => {
+                options.landscape = true;
+                rest
+            }
+            ("--margin", [margin, rest @ ..]) => {
+                options.margin_mm = margin
+                    .parse()
+                    .map_err(|_| format!("Invalid margin: {}", margin))?;
+                rest
+            }
+            ("--caption", [text, rest @ ..]) => {
+                caption = Some(text.clone());
+                rest
+            }
+            _ => return Err(USAGE.to_string()),
+        };
+    }
+    Ok((options, caption))
+}
+
+fn plotter_options(mut flags: &[String]) -> Result<plotter::Options, String> {
+    let mut options = plotter::Options::default();
+    while let Some((flag, rest)) = flags.split_first() {
+        flags = match (flag.as_str(), rest) {
+            ("--width", [width, rest @ ..]) => {
+                options.width_mm = parse_number(width)?;
+                rest
+            }
+            ("--draw-speed", [speed, rest @ ..]) => {
+                options.draw_speed = parse_number(speed)?;
+                rest
+            }
+            ("--travel-speed", [speed, rest @ ..]) => {
+                options.travel_speed = parse_number(speed)?;
+                rest
+            }
+            ("--pen-up", [command, rest @ ..]) => {
+                options.pen_up = command.clone();
+                rest
+            }
+            ("--pen-down", [command, rest @ ..]) => {
+                options.pen_down = command.clone();
+                rest
+            }
+            ("--points", rest) => {
+                options.include_points = true;
+                rest
+            }
+            _ => return Err(USAGE.to_string()),
+        };
+    }
+    Ok(options)
+}
+
+fn dxf_options(mut flags: &[String]) -> Result<dxf::Options, String> {
+    let mut options = dxf::Options::default();
+    while let Some((flag, rest)) = flags.split_first() {
+        flags = match (flag.as_str(), rest) {
+            ("--width", [width, rest @ ..]) => {
+                options.width_mm = parse_number(width)?;
+                rest
+            }
+            ("--points", rest) => {
+                options.include_points = true;
+                rest
+            }
+            ("--outline", rest) => {
+                options.outline = true;
+                rest
+            }
+            _ => return Err(USAGE.to_string()),
+        };
+    }
+    Ok(options)
+}
+
+fn parse_number(value: &str) -> Result<f64, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|value: &f64| *value > 0_f64)
+        .ok_or(format!("Invalid number: {}", value))
+}
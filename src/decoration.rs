@@ -0,0 +1,161 @@
+use std::f64::consts::FRAC_PI_2;
+
+use serde::{Deserialize, Serialize};
+
+use crate::render::Colour;
+
+/// Straight edges used to approximate each rounded corner
+const CORNER_SEGMENTS: usize = 8;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum Fill {
+    /// Transparent, leaving any background to the export
+    #[default]
+    None,
+    Solid,
+    /// Fading from top to bottom
+    Gradient,
+}
+
+impl Fill {
+    pub const ALL: [Fill; 3] = [Fill::None, Fill::Solid, Fill::Gradient];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Fill::None => "None",
+            Fill::Solid => "Solid",
+            Fill::Gradient => "Gradient",
+        }
+    }
+}
+
+/// What the canvas is filled with behind the lines
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(default)]
+pub struct Background {
+    pub fill: Fill,
+    pub colour: Colour,
+    /// Colour a gradient fades to at the bottom
+    pub gradient_to: Colour,
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background {
+            fill: Fill::None,
+            colour: Colour::WHITE,
+            gradient_to: Colour {
+                r: 208,
+                g: 224,
+                b: 240,
+            },
+        }
+    }
+}
+
+impl Background {
+    /// Colours at the top & bottom of the canvas, the same for a solid fill
+    pub fn colours(&self) -> Option<(Colour, Colour)> {
+        match self.fill {
+            Fill::None => None,
+            Fill::Solid => Some((self.colour, self.colour)),
+            Fill::Gradient => Some((self.colour, self.gradient_to)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum Corners {
+    #[default]
+    Square,
+    Rounded,
+    /// Cut off at 45°
+    Cut,
+}
+
+impl Corners {
+    pub const ALL: [Corners; 3] = [Corners::Square, Corners::Rounded, Corners::Cut];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Corners::Square => "Square",
+            Corners::Rounded => "Rounded",
+            Corners::Cut => "Cut",
+        }
+    }
+}
+
+/// A border drawn around the canvas
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(default)]
+pub struct Frame {
+    pub shown: bool,
+    pub colour: Colour,
+    pub width: f64,
+    /// Distance from the edge of the canvas to the middle of the frame's line
+    pub inset: f64,
+    pub corners: Corners,
+    /// Radius of rounded corners, or how far along each side cut corners start
+    pub corner_size: f64,
+}
+
+impl Default for Frame {
+    fn default() -> Self {
+        Frame {
+            shown: false,
+            colour: Colour::BLACK,
+            width: 10_f64,
+            inset: 40_f64,
+            corners: Corners::Square,
+            corner_size: 40_f64,
+        }
+    }
+}
+
+impl Frame {
+    /// Corners of the frame's outline on a canvas of the given size, clockwise from the top
+    /// left, or `None` if there's no room for it
+    pub fn points(&self, width: f64, height: f64) -> Option<Vec<(f64, f64)>> {
+        let (left, top) = (self.inset, self.inset);
+        let (right, bottom) = (width - self.inset, height - self.inset);
+        if right <= left || bottom <= top {
+            return None;
+        }
+        let size = self
+            .corner_size
+            .max(0_f64)
+            .min((right - left) / 2_f64)
+            .min((bottom - top) / 2_f64);
+        // Each corner, with the directions to step in from it along the sides before & after
+        let corners = [
+            ((left, top), (0_f64, 1_f64), (1_f64, 0_f64)),
+            ((right, top), (-1_f64, 0_f64), (0_f64, 1_f64)),
+            ((right, bottom), (0_f64, -1_f64), (-1_f64, 0_f64)),
+            ((left, bottom), (1_f64, 0_f64), (0_f64, -1_f64)),
+        ];
+
+        let mut points = Vec::new();
+        for ((x, y), (bx, by), (ax, ay)) in corners.iter() {
+            match self.corners {
+                _ if size == 0_f64 => points.push((*x, *y)),
+                Corners::Square => points.push((*x, *y)),
+                Corners::Cut => {
+                    points.push((x + bx * size, y + by * size));
+                    points.push((x + ax * size, y + ay * size));
+                }
+                Corners::Rounded => {
+                    let centre = (x + (ax + bx) * size, y + (ay + by) * size);
+                    for step in 0..=CORNER_SEGMENTS {
+                        let angle = FRAC_PI_2 * step as f64 / CORNER_SEGMENTS as f64;
+                        // Sweep from the side before the corner to the side after it
+                        points.push((
+                            centre.0 - ax * size * angle.cos() - bx * size * angle.sin(),
+                            centre.1 - ay * size * angle.cos() - by * size * angle.sin(),
+                        ));
+                    }
+                }
+            }
+        }
+        Some(points)
+    }
+}
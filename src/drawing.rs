@@ -1,98 +1,1140 @@
-use seed::{prelude::*, *};
+use seed::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use crate::decoration::{Background, Frame};
+use crate::marker::{Markers, STATION_LINES};
+use crate::render::{self, Colour, Scene, Shape};
+use crate::renderer::{sketch, transit, Renderer};
+
+/// Distance between neighbouring grid points when the canvas is fitted to the grid
+const FIT_SPACING: f64 = 200_f64;
+/// Width of the canvas for the fixed aspect ratio presets
+const PRESET_WIDTH: f64 = 1000_f64;
+const DEFAULT_MARGIN: f64 = 100_f64;
+/// Radius of the dots marking grid points and line ends
+pub const POINT_RADIUS: f64 = 10_f64;
+pub const LINE_WIDTH: f64 = 20_f64;
+
+/// Largest number of columns or rows a grid can have
+pub const MAX_GRID_SIZE: u16 = 64;
+/// Steps points are measured in between neighbouring grid points, fine enough for halves,
+/// thirds & custom points placed anywhere
+pub const CELL_STEPS: i16 = 60;
+
+/// Column & row of a point in steps from the top left grid point, `CELL_STEPS` to a cell
+pub type Point = (i16, i16);
+pub type Line = (Point, Point);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DrawingError {
+    /// The point, in steps from the top left grid point, doesn't lie within the grid. Wider
+    /// than a `Point` so typed numbers too big for one are reported as typed
+    OutOfRange((i32, i32)),
+    /// A point was requested relative to the last line, but there are no lines yet
+    NoLines,
+    /// Lines can't be added to the active layer while it's locked
+    Locked,
+    /// The symmetry only works on square grids
+    NotSquare,
+}
+
+impl fmt::Display for DrawingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DrawingError::OutOfRange((x, y)) => {
+                // Numbered from 1 like the grid's columns & rows, to two decimal places
+                let number = |steps: i32| {
+                    ((steps as f64 / CELL_STEPS as f64 + 1_f64) * 100_f64).round() / 100_f64
+                };
+                write!(
+                    f,
+                    "Point {},{} is outside of the grid",
+                    number(*x),
+                    number(*y)
+                )
+            }
+            DrawingError::NoLines => write!(f, "There is no previous line"),
+            DrawingError::Locked => write!(f, "Locked layers can't be changed"),
+            DrawingError::NotSquare => write!(f, "The symmetry needs a square grid"),
+        }
+    }
+}
+
+/// A way of addressing a grid point, with columns & rows numbered from 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PointRef {
+    At {
+        column: u16,
+        row: u16,
+    },
+    /// The end of the most recently added line
+    LastEndpoint,
+    /// A column on the second to last row, which new lines are drawn down from
+    PreviousRow(u16),
+}
+
+/// The shape of the canvas a drawing is laid out on
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Canvas {
+    /// Grows with the grid, keeping every cell square
+    #[default]
+    Fit,
+    A4Portrait,
+    A4Landscape,
+    Square,
+    Widescreen,
+}
+
+impl Canvas {
+    pub const ALL: [Canvas; 5] = [
+        Canvas::Fit,
+        Canvas::A4Portrait,
+        Canvas::A4Landscape,
+        Canvas::Square,
+        Canvas::Widescreen,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Canvas::Fit => "Fit to grid",
+            Canvas::A4Portrait => "A4 portrait",
+            Canvas::A4Landscape => "A4 landscape",
+            Canvas::Square => "Square",
+            Canvas::Widescreen => "16:9",
+        }
+    }
+
+    /// Width divided by height, `None` if the canvas is sized from the grid
+    fn aspect_ratio(&self) -> Option<f64> {
+        match self {
+            Canvas::Fit => None,
+            Canvas::A4Portrait => Some(210_f64 / 297_f64),
+            Canvas::A4Landscape => Some(297_f64 / 210_f64),
+            Canvas::Square => Some(1_f64),
+            Canvas::Widescreen => Some(16_f64 / 9_f64),
+        }
+    }
+}
+
+/// The points between grid points that lines can be drawn to
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Subgrid {
+    #[default]
+    Whole,
+    Halves,
+    Thirds,
+}
+
+impl Subgrid {
+    pub const ALL: [Subgrid; 3] = [Subgrid::Whole, Subgrid::Halves, Subgrid::Thirds];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Subgrid::Whole => "Grid points",
+            Subgrid::Halves => "Halves",
+            Subgrid::Thirds => "Thirds",
+        }
+    }
+
+    /// Steps between neighbouring sub-grid points
+    pub fn step(&self) -> i16 {
+        match self {
+            Subgrid::Whole => CELL_STEPS,
+            Subgrid::Halves => CELL_STEPS / 2,
+            Subgrid::Thirds => CELL_STEPS / 3,
+        }
+    }
+}
+
+fn default_margin() -> f64 {
+    DEFAULT_MARGIN
+}
+
+/// Drawings saved before points were measured in steps were measured in whole cells
+fn legacy_point_steps() -> i16 {
+    1
+}
+
+/// A named set of lines drawn in their own style, which can be hidden or locked
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Layer {
+    pub name: String,
+    pub visible: bool,
+    /// Stops lines being added to the layer
+    pub locked: bool,
+    pub colour: Colour,
+    pub line_width: f64,
+    lines: Vec<Line>,
+}
+
+impl Layer {
+    pub fn new(name: String) -> Layer {
+        Layer {
+            name,
+            visible: true,
+            locked: false,
+            colour: Colour::BLACK,
+            line_width: LINE_WIDTH,
+            lines: vec![],
+        }
+    }
+
+    pub fn lines(&self) -> &[Line] {
+        &self.lines
+    }
+
+    fn hash(&self, hasher: &mut impl Hasher) {
+        self.visible.hash(hasher);
+        (self.colour.r, self.colour.g, self.colour.b).hash(hasher);
+        self.line_width.to_bits().hash(hasher);
+        self.lines.hash(hasher);
+    }
+}
 
 #[derive(Serialize, Deserialize, Clone)]
+#[serde(from = "SavedDrawing")]
 pub struct Drawing {
+    pub title: String,
+    pub description: String,
+    tags: Vec<String>,
+    /// When the drawing was first saved & last changed, as ISO 8601 timestamps
+    pub created: Option<String>,
+    pub modified: Option<String>,
+    /// Seed the drawing's random lines were generated from, if it was remixed, which also sets
+    /// how sketched lines wobble
+    pub seed: Option<u64>,
+
     pub grid_width: u16,
     pub grid_height: u16,
 
-    pub view_width: f64,
-    pub view_height: f64,
+    canvas: Canvas,
+    /// Space between the outermost grid points and the edge of the canvas
+    margin: f64,
+    pub background: Background,
+    pub frame: Frame,
+    pub markers: Markers,
+    pub renderer: Renderer,
 
     pub includes_points: bool,
+    pub subgrid: Subgrid,
+    /// Points placed anywhere within the grid, which lines can be drawn to & are marked like
+    /// grid points
+    custom_points: Vec<Point>,
+    /// Steps to a cell the points were saved in, always `CELL_STEPS` once loaded
+    point_steps: i16,
+
+    /// Drawn in order, so later layers are on top. There's always at least one
+    layers: Vec<Layer>,
+    /// The layer new lines are added to
+    active_layer: usize,
+}
 
-    lines: Vec<((i16, i16), (i16, i16))>,
+/// A drawing as it's stored, which may have been saved before drawings had layers
+#[derive(Deserialize)]
+struct SavedDrawing {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    created: Option<String>,
+    #[serde(default)]
+    modified: Option<String>,
+    #[serde(default)]
+    seed: Option<u64>,
+    grid_width: u16,
+    grid_height: u16,
+    #[serde(default)]
+    canvas: Canvas,
+    #[serde(default = "default_margin")]
+    margin: f64,
+    #[serde(default)]
+    background: Background,
+    #[serde(default)]
+    frame: Frame,
+    #[serde(default)]
+    markers: Markers,
+    #[serde(default)]
+    renderer: Renderer,
+    includes_points: bool,
+    #[serde(default)]
+    subgrid: Subgrid,
+    #[serde(default)]
+    custom_points: Vec<Point>,
+    #[serde(default = "legacy_point_steps")]
+    point_steps: i16,
+    /// The lines of a drawing saved without layers
+    #[serde(default)]
+    lines: Vec<Line>,
+    #[serde(default)]
+    layers: Vec<Layer>,
+    #[serde(default)]
+    active_layer: usize,
+}
+
+impl From<SavedDrawing> for Drawing {
+    fn from(saved: SavedDrawing) -> Drawing {
+        let mut layers = saved.layers;
+        if layers.is_empty() {
+            let mut layer = Layer::new("Layer 1".to_string());
+            layer.lines = saved.lines;
+            layers.push(layer);
+        }
+        // Convert points saved in fewer steps, such as whole cells, to `CELL_STEPS`
+        let scale = CELL_STEPS / saved.point_steps.clamp(1, CELL_STEPS);
+        let scale_point = |(x, y): Point| (x.saturating_mul(scale), y.saturating_mul(scale));
+        for layer in &mut layers {
+            for (from, to) in &mut layer.lines {
+                *from = scale_point(*from);
+                *to = scale_point(*to);
+            }
+        }
+        Drawing {
+            title: saved.title,
+            description: saved.description,
+            tags: saved.tags,
+            created: saved.created,
+            modified: saved.modified,
+            seed: saved.seed,
+            grid_width: saved.grid_width,
+            grid_height: saved.grid_height,
+            canvas: saved.canvas,
+            margin: saved.margin,
+            background: saved.background,
+            frame: saved.frame,
+            markers: saved.markers,
+            renderer: saved.renderer,
+            includes_points: saved.includes_points,
+            subgrid: saved.subgrid,
+            custom_points: saved.custom_points.into_iter().map(scale_point).collect(),
+            point_steps: CELL_STEPS,
+            active_layer: saved.active_layer.min(layers.len() - 1),
+            layers,
+        }
+    }
+}
+
+impl Default for Drawing {
+    fn default() -> Self {
+        Drawing::new()
+    }
 }
 
 impl Drawing {
     pub fn new() -> Drawing {
         Drawing {
+            title: String::new(),
+            description: String::new(),
+            tags: vec![],
+            created: None,
+            modified: None,
+            seed: None,
             grid_width: 4,
             grid_height: 2,
-            view_width: 1000_f64,
-            view_height: 2000_f64,
-            lines: vec![],
+            canvas: Canvas::Fit,
+            margin: DEFAULT_MARGIN,
+            background: Background::default(),
+            frame: Frame::default(),
+            markers: Markers::default(),
+            renderer: Renderer::Plain,
             includes_points: true,
+            subgrid: Subgrid::Whole,
+            custom_points: vec![],
+            point_steps: CELL_STEPS,
+            layers: vec![Layer::new("Layer 1".to_string())],
+            active_layer: 0,
+        }
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// Sets the tags from comma separated text, ignoring blanks & repeats
+    pub fn set_tags(&mut self, text: &str) {
+        self.tags.clear();
+        for tag in text.split(',').map(str::trim).filter(|tag| !tag.is_empty()) {
+            if !self
+                .tags
+                .iter()
+                .any(|existing| existing.eq_ignore_ascii_case(tag))
+            {
+                self.tags.push(tag.to_string());
+            }
+        }
+    }
+
+    /// Adds a line to the active layer
+    pub fn add_line(&mut self, from: Point, to: Point) -> Result<(), DrawingError> {
+        self.add_lines(&[(from, to)])
+    }
+
+    /// Adds lines to the active layer, all of them or, if any of them can't be, none
+    pub fn add_lines(&mut self, lines: &[Line]) -> Result<(), DrawingError> {
+        for (from, to) in lines {
+            self.check(*from)?;
+            self.check(*to)?;
+        }
+        let layer = &mut self.layers[self.active_layer];
+        if layer.locked {
+            return Err(DrawingError::Locked);
+        }
+        layer.lines.extend_from_slice(lines);
+        Ok(())
+    }
+
+    /// Lines of the active layer
+    pub fn lines(&self) -> &[Line] {
+        &self.layers[self.active_layer].lines
+    }
+
+    /// Lines of every layer that's shown, in drawing order
+    pub fn visible_lines(&self) -> impl Iterator<Item = &Line> {
+        self.layers
+            .iter()
+            .filter(|layer| layer.visible)
+            .flat_map(|layer| layer.lines.iter())
+    }
+
+    /// Number of lines across every layer
+    pub fn line_count(&self) -> usize {
+        self.layers.iter().map(|layer| layer.lines.len()).sum()
+    }
+
+    /// Swaps in lines of the active layer known to be within the grid, such as generated ones
+    pub(crate) fn replace_lines(&mut self, lines: Vec<Line>) -> Result<(), DrawingError> {
+        let layer = &mut self.layers[self.active_layer];
+        if layer.locked {
+            return Err(DrawingError::Locked);
+        }
+        layer.lines = lines;
+        Ok(())
+    }
+
+    /// Moves the ends of every line of every layer along with the custom points, the result
+    /// being kept within the grid, unless that would change a locked layer
+    pub(crate) fn map_points(&mut self, map: impl Fn(Point) -> Point) -> Result<(), DrawingError> {
+        let mut mapped = self.clone();
+        for layer in &mut mapped.layers {
+            layer.lines = layer
+                .lines
+                .iter()
+                .map(|(from, to)| (map(*from), map(*to)))
+                .collect();
+        }
+        let mut custom_points: Vec<Point> = Vec::with_capacity(self.custom_points.len());
+        for point in self.custom_points.iter().map(|point| map(*point)) {
+            if !custom_points.contains(&point) {
+                custom_points.push(point);
+            }
+        }
+        mapped.custom_points = custom_points;
+        mapped.drop_lines_outside_grid();
+        self.check_locks(&mapped)?;
+        *self = mapped;
+        Ok(())
+    }
+
+    /// Refuses a change that moves or drops the lines of a locked layer
+    fn check_locks(&self, changed: &Drawing) -> Result<(), DrawingError> {
+        let unchanged = self
+            .layers
+            .iter()
+            .zip(&changed.layers)
+            .all(|(before, after)| !before.locked || before.lines == after.lines);
+        if unchanged {
+            Ok(())
+        } else {
+            Err(DrawingError::Locked)
+        }
+    }
+
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    /// Lets a layer's name & style be changed, its lines only changing through the drawing
+    pub fn layer_mut(&mut self, idx: usize) -> Option<&mut Layer> {
+        self.layers.get_mut(idx)
+    }
+
+    pub fn active_layer(&self) -> usize {
+        self.active_layer
+    }
+
+    pub fn set_active_layer(&mut self, idx: usize) {
+        self.active_layer = idx.min(self.layers.len() - 1);
+    }
+
+    /// Adds an empty layer on top & makes it the active one
+    pub fn add_layer(&mut self) {
+        let name = (1..)
+            .map(|n| format!("Layer {}", n))
+            .find(|name| !self.layers.iter().any(|layer| layer.name == *name))
+            .unwrap();
+        self.layers.push(Layer::new(name));
+        self.active_layer = self.layers.len() - 1;
+    }
+
+    /// Removes a layer & its lines, unless it's the only one
+    pub fn remove_layer(&mut self, idx: usize) {
+        if self.layers.len() > 1 && idx < self.layers.len() {
+            self.layers.remove(idx);
+            if self.active_layer > idx || self.active_layer == self.layers.len() {
+                self.active_layer -= 1;
+            }
+        }
+    }
+
+    /// A copy of the drawing with only the one layer, shown whether or not it's hidden
+    pub fn layer_drawing(&self, idx: usize) -> Option<Drawing> {
+        let mut layer = self.layers.get(idx)?.clone();
+        layer.visible = true;
+        Some(Drawing {
+            layers: vec![layer],
+            active_layer: 0,
+            ..self.clone()
+        })
+    }
+
+    /// Every point of the grid, row by row
+    pub fn grid(&self) -> impl Iterator<Item = Point> {
+        self.lattice(CELL_STEPS)
+    }
+
+    /// Every point of the sub-grid, including the grid points, row by row
+    pub fn subgrid_points(&self) -> impl Iterator<Item = Point> {
+        self.lattice(self.subgrid.step())
+    }
+
+    fn lattice(&self, step: i16) -> impl Iterator<Item = Point> {
+        let (width, height) = self.extent();
+        (0..=height / step).flat_map(move |y| (0..=width / step).map(move |x| (x * step, y * step)))
+    }
+
+    /// Every point lines can be drawn to, being the sub-grid then the custom points
+    pub fn snap_points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.subgrid_points()
+            .chain(self.custom_points.iter().copied())
+    }
+
+    /// The grid & custom points, which are marked when the drawing includes its points
+    pub fn marked_points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.grid().chain(self.custom_points.iter().copied())
+    }
+
+    pub fn custom_points(&self) -> &[Point] {
+        &self.custom_points
+    }
+
+    pub fn add_custom_point(&mut self, point: Point) -> Result<(), DrawingError> {
+        self.check(point)?;
+        if !self.custom_points.contains(&point) {
+            self.custom_points.push(point);
+        }
+        Ok(())
+    }
+
+    /// Removes every custom point, keeping any lines drawn to them
+    pub fn clear_custom_points(&mut self) {
+        self.custom_points.clear();
+    }
+
+    /// The bottom right grid point, in steps from the top left one
+    pub fn extent(&self) -> Point {
+        (
+            (self.grid_width as i16 - 1) * CELL_STEPS,
+            (self.grid_height as i16 - 1) * CELL_STEPS,
+        )
+    }
+
+    pub fn contains(&self, (x, y): Point) -> bool {
+        let (width, height) = self.extent();
+        x >= 0 && y >= 0 && x <= width && y <= height
+    }
+
+    fn check(&self, point: Point) -> Result<Point, DrawingError> {
+        if self.contains(point) {
+            Ok(point)
+        } else {
+            Err(DrawingError::OutOfRange((point.0.into(), point.1.into())))
+        }
+    }
+
+    /// The grid point at a column & row numbered from 1, checked before converting to steps
+    /// so numbers of any size are an error rather than overflowing
+    fn grid_point(&self, column: u16, row: u16) -> Result<Point, DrawingError> {
+        let steps = |number: u16| (number as i32 - 1) * CELL_STEPS as i32;
+        if (1..=self.grid_width).contains(&column) && (1..=self.grid_height).contains(&row) {
+            Ok((steps(column) as i16, steps(row) as i16))
+        } else {
+            Err(DrawingError::OutOfRange((steps(column), steps(row))))
         }
     }
 
-    pub fn add_line(&mut self, from: (i16, i16), to: (i16, i16)) {
-        self.lines.push((from, to));
+    /// Finds the grid point a `PointRef` refers to
+    pub fn resolve(&self, point: PointRef) -> Result<Point, DrawingError> {
+        match point {
+            PointRef::At { column, row } => self.grid_point(column, row),
+            PointRef::LastEndpoint => self
+                .lines()
+                .last()
+                .map(|(_, to)| *to)
+                .ok_or(DrawingError::NoLines)
+                .and_then(|to| self.check(to)),
+            PointRef::PreviousRow(column) => {
+                self.grid_point(column, self.grid_height.saturating_sub(1).max(1))
+            }
+        }
+    }
+
+    /// Sets the number of columns, dropping any lines that no longer fit in the grid unless
+    /// they're locked
+    pub fn set_grid_width(&mut self, width: u16) -> Result<(), DrawingError> {
+        self.resize_grid(width, self.grid_height)
+    }
+
+    /// Sets the number of rows, dropping any lines that no longer fit in the grid unless
+    /// they're locked
+    pub fn set_grid_height(&mut self, height: u16) -> Result<(), DrawingError> {
+        self.resize_grid(self.grid_width, height)
+    }
+
+    fn resize_grid(&mut self, width: u16, height: u16) -> Result<(), DrawingError> {
+        let mut resized = self.clone();
+        resized.grid_width = width.clamp(1, MAX_GRID_SIZE);
+        resized.grid_height = height.clamp(1, MAX_GRID_SIZE);
+        resized.drop_lines_outside_grid();
+        self.check_locks(&resized)?;
+        *self = resized;
+        Ok(())
+    }
+
+    /// Drops the lines & custom points that no longer fit in the grid
+    fn drop_lines_outside_grid(&mut self) {
+        let (width, height) = self.extent();
+        let contains = |(x, y): Point| x >= 0 && y >= 0 && x <= width && y <= height;
+        for layer in &mut self.layers {
+            layer
+                .lines
+                .retain(|(from, to)| contains(*from) && contains(*to));
+        }
+        self.custom_points.retain(|point| contains(*point));
+    }
+
+    pub fn add_row(&mut self) {
+        self.grid_height = (self.grid_height + 1).min(MAX_GRID_SIZE);
+    }
+
+    /// Removes the bottom row, along with any lines touching it unless they're locked
+    pub fn remove_row(&mut self) -> Result<(), DrawingError> {
+        self.set_grid_height(self.grid_height - 1)
     }
 
     pub fn toggle_include_points(&mut self) {
         self.includes_points = !self.includes_points;
     }
 
+    pub fn canvas(&self) -> Canvas {
+        self.canvas
+    }
+
+    pub fn set_canvas(&mut self, canvas: Canvas) {
+        self.canvas = canvas;
+        self.set_margin(self.margin);
+    }
+
+    pub fn margin(&self) -> f64 {
+        self.margin
+    }
+
+    /// Sets the margin, keeping it within half of the smallest side of a preset canvas
+    pub fn set_margin(&mut self, margin: f64) {
+        let max = match self.canvas.aspect_ratio() {
+            Some(_) => self.view_width().min(self.view_height()) / 2_f64 - 1_f64,
+            None => f64::MAX,
+        };
+        self.margin = margin.max(0_f64).min(max);
+    }
+
+    /// Width of the canvas, a fitted one being at least a cell wide so that a single column
+    /// without a margin still has a size to scale by
+    pub fn view_width(&self) -> f64 {
+        match self.canvas.aspect_ratio() {
+            Some(_) => PRESET_WIDTH,
+            None => fit_extent(self.margin, self.grid_width),
+        }
+    }
+
+    /// Height of the canvas, a fitted one being at least a cell tall
+    pub fn view_height(&self) -> f64 {
+        match self.canvas.aspect_ratio() {
+            Some(ratio) => PRESET_WIDTH / ratio,
+            None => fit_extent(self.margin, self.grid_height),
+        }
+    }
+
     pub fn x_spacing(&self) -> f64 {
-        self.view_width / (self.grid_width + 1) as f64
+        spacing(self.view_width() - 2_f64 * self.margin, self.grid_width)
     }
 
     pub fn y_spacing(&self) -> f64 {
-        self.view_width / (self.grid_width + 1) as f64
+        spacing(self.view_height() - 2_f64 * self.margin, self.grid_height)
     }
 
-    pub fn draw<Msg>(&self) -> Vec<Node<Msg>> {
-        let mut ret = if self.includes_points {
-            self.gen_circles()
+    /// Position of a point on the canvas
+    pub fn position(&self, (x, y): Point) -> (f64, f64) {
+        let origin_x = if self.grid_width > 1 {
+            self.margin
         } else {
-            Vec::new()
+            self.view_width() / 2_f64
         };
+        let origin_y = if self.grid_height > 1 {
+            self.margin
+        } else {
+            self.view_height() / 2_f64
+        };
+        let steps = CELL_STEPS as f64;
+        (
+            origin_x + self.x_spacing() * x as f64 / steps,
+            origin_y + self.y_spacing() * y as f64 / steps,
+        )
+    }
 
-        self.lines
-            .iter()
-            .map(|coords| self.draw_line(*coords))
-            .for_each(|mut node| ret.append(&mut node));
+    /// The point nearest a position on the canvas, placed freely within the grid
+    pub fn point_at(&self, (x, y): (f64, f64)) -> Point {
+        let (origin_x, origin_y) = self.position((0, 0));
+        let (width, height) = self.extent();
+        let steps = |offset: f64, spacing: f64, max: i16| {
+            if spacing > 0_f64 {
+                ((offset / spacing * CELL_STEPS as f64).round() as i16).clamp(0, max)
+            } else {
+                0
+            }
+        };
+        (
+            steps(x - origin_x, self.x_spacing(), width),
+            steps(y - origin_y, self.y_spacing(), height),
+        )
+    }
+
+    /// The point lines can be drawn to nearest a position on the canvas
+    pub fn nearest_snap_point(&self, position: (f64, f64)) -> Point {
+        let distance = |point: &Point| {
+            let (x, y) = self.position(*point);
+            (x - position.0).hypot(y - position.1)
+        };
+        self.snap_points()
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .unwrap_or((0, 0))
+    }
+
+    /// Hash of everything that changes how the drawing looks, for caching renders of it
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.grid_width.hash(&mut hasher);
+        self.grid_height.hash(&mut hasher);
+        self.canvas.hash(&mut hasher);
+        self.margin.to_bits().hash(&mut hasher);
+        self.includes_points.hash(&mut hasher);
+        self.renderer.hash(&mut hasher);
+        self.custom_points.hash(&mut hasher);
+        // These are small & made of floats, so their debug output stands in for hashing
+        format!("{:?} {:?} {:?}", self.background, self.frame, self.markers).hash(&mut hasher);
+        self.layers.iter().for_each(|layer| layer.hash(&mut hasher));
+        hasher.finish()
+    }
+
+    pub fn draw<Msg>(&self) -> Vec<Node<Msg>> {
+        self.scene().nodes()
+    }
 
-        ret
+    /// Draws a line that's yet to be added, in the style of the active layer
+    pub fn draw_line<Msg>(&self, (from, to): Line) -> Vec<Node<Msg>> {
+        let layer = &self.layers[self.active_layer];
+        let mut shapes = vec![Shape::Line {
+            from: self.position(from),
+            to: self.position(to),
+            width: layer.line_width,
+        }];
+        shapes.extend(
+            self.markers
+                .endpoint
+                .shapes(vec![self.position(from), self.position(to)]),
+        );
+        render::shape_nodes(&[Shape::Group {
+            id: Some("next-line".to_string()),
+            colour: layer.colour,
+            shapes,
+        }])
     }
 
-    fn draw_line<Msg>(
+    /// Lays the drawing out as shapes on its canvas
+    pub fn scene(&self) -> Scene {
+        self.scene_with_points(self.includes_points)
+    }
+
+    /// Lays the drawing out as shapes, overriding whether the grid points are shown
+    pub fn scene_with_points(&self, include_points: bool) -> Scene {
+        self.layout(include_points, self.renderer)
+    }
+
+    /// Lays the drawing out for plotting & cutting, without effects such as sketchy strokes
+    pub fn machine_scene(&self, include_points: bool) -> Scene {
+        self.layout(include_points, self.renderer.for_machines())
+    }
+
+    /// The visible lines as single pen strokes, following the routes of a transit map
+    pub fn machine_paths(&self) -> Vec<Vec<(f64, f64)>> {
+        match self.renderer.for_machines() {
+            Renderer::Transit => transit::paths(self),
+            Renderer::Plain | Renderer::Sketch => self
+                .visible_lines()
+                .map(|(from, to)| vec![self.position(*from), self.position(*to)])
+                .collect(),
+        }
+    }
+
+    fn layout(&self, include_points: bool, renderer: Renderer) -> Scene {
+        // How many line ends are at each used point, & the top layer with one there
+        let mut ends: BTreeMap<Point, (usize, usize)> = BTreeMap::new();
+        for (idx, layer) in self.layers.iter().enumerate() {
+            if layer.visible {
+                for point in layer.lines.iter().flat_map(|(from, to)| [*from, *to]) {
+                    let (count, top) = ends.entry(point).or_insert((0, idx));
+                    *count += 1;
+                    *top = idx;
+                }
+            }
+        }
+
+        let mut shapes = Vec::new();
+        if let Some((top, bottom)) = self.background.colours() {
+            shapes.push(Shape::Fill { top, bottom });
+        }
+        if include_points {
+            let unused = self
+                .marked_points()
+                .filter(|point| !ends.contains_key(point))
+                .map(|point| self.position(point))
+                .collect();
+            shapes.extend(self.markers.unused.shapes(unused));
+        }
+
+        match renderer {
+            Renderer::Plain => shapes.extend(self.layer_groups(&ends, |_, layer, (from, to)| {
+                vec![Shape::Line {
+                    from: self.position(*from),
+                    to: self.position(*to),
+                    width: layer.line_width,
+                }]
+            })),
+            Renderer::Transit => shapes.extend(transit::shapes(self)),
+            Renderer::Sketch => {
+                // Drawings are given a seed when switched to sketching, though one exported
+                // as a sketch without being switched goes by its content instead
+                let seed = self.seed.unwrap_or_else(|| self.content_hash());
+                shapes.extend(self.layer_groups(&ends, |idx, layer, line| {
+                    sketch::strokes(
+                        self.position(line.0),
+                        self.position(line.1),
+                        layer.line_width,
+                        sketch::line_seed(seed, idx, *line),
+                    )
+                }))
+            }
+        }
+        if let Some(points) = self.frame_points() {
+            shapes.push(Shape::Group {
+                id: Some("frame".to_string()),
+                colour: self.frame.colour,
+                shapes: vec![Shape::Polygon {
+                    points,
+                    width: self.frame.width,
+                }],
+            });
+        }
+
+        Scene {
+            width: self.view_width(),
+            height: self.view_height(),
+            shapes,
+        }
+    }
+
+    /// A group for each visible layer, with each line drawn by `line_shapes` & the line ends
+    /// marked
+    fn layer_groups(
         &self,
-        ((from_x, from_y), (to_x, to_y)): ((i16, i16), (i16, i16)),
-    ) -> Vec<Node<Msg>> {
-        let mut ret = Vec::new();
-
-        let from_x = self.x_spacing() * (from_x + 1) as f64;
-        let from_y = self.y_spacing() * (from_y + 1) as f64;
-        let to_x = self.x_spacing() * (to_x + 1) as f64;
-        let to_y = self.y_spacing() * (to_y + 1) as f64;
-
-        ret.push(line_![
-        attrs! {At::X1 => from_x, At::Y1 => from_y, At::X2 => to_x, At::Y2 => to_y, At::Stroke => "black", At::StrokeWidth => 20}
-    ]);
-        ret.push(circle![
-            attrs! {At::Cx => from_x, At::Cy => from_y, At::R => 10}
-        ]);
-        ret.push(circle![
-            attrs! {At::Cx => to_x, At::Cy => to_y, At::R => 10}
-        ]);
-        ret
-    }
-
-    fn gen_circles<Msg>(&self) -> Vec<Node<Msg>> {
-        let mut ret = Vec::new();
-
-        for i in 0..self.grid_height {
-            for j in 0..self.grid_width {
-                let x_offset = self.x_spacing() * (j as f64 + 1_f64);
-                let y_offset = self.y_spacing() * (i as f64 + 1_f64);
-
-                ret.push(circle![
-                    attrs! {At::Cx => x_offset, At::Cy => y_offset, At::R => 10}
-                ])
+        ends: &BTreeMap<Point, (usize, usize)>,
+        line_shapes: impl Fn(usize, &Layer, &Line) -> Vec<Shape>,
+    ) -> Vec<Shape> {
+        let mut shapes = Vec::new();
+        for (idx, layer) in self.layers.iter().enumerate() {
+            if !layer.visible {
+                continue;
             }
+            let mut layer_shapes: Vec<Shape> = layer
+                .lines
+                .iter()
+                .flat_map(|line| line_shapes(idx, layer, line))
+                .collect();
+            let marked = |station: bool| {
+                ends.iter()
+                    .filter(|(_, (count, top))| *top == idx && (*count >= STATION_LINES) == station)
+                    .map(|(point, _)| self.position(*point))
+                    .collect()
+            };
+            layer_shapes.extend(self.markers.endpoint.shapes(marked(false)));
+            layer_shapes.extend(self.markers.station.shapes(marked(true)));
+            shapes.push(Shape::Group {
+                id: Some(format!("layer-{}", idx + 1)),
+                colour: layer.colour,
+                shapes: layer_shapes,
+            });
+        }
+        shapes
+    }
+
+    /// Corners of the frame on the canvas, if it's shown
+    pub fn frame_points(&self) -> Option<Vec<(f64, f64)>> {
+        if self.frame.shown && self.frame.width > 0_f64 {
+            self.frame.points(self.view_width(), self.view_height())
+        } else {
+            None
+        }
+    }
+}
+
+/// Distance between neighbouring points when `points` are spread evenly across `extent`
+fn fit_extent(margin: f64, points: u16) -> f64 {
+    (2_f64 * margin + FIT_SPACING * (points.max(1) - 1) as f64).max(FIT_SPACING)
+}
+
+fn spacing(extent: f64, points: u16) -> f64 {
+    if points > 1 {
+        extent / (points - 1) as f64
+    } else {
+        0_f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drawing(width: u16, height: u16) -> Drawing {
+        let mut drawing = Drawing::new();
+        drawing.set_grid_width(width).unwrap();
+        drawing.set_grid_height(height).unwrap();
+        drawing
+    }
+
+    #[test]
+    fn resolve_rejects_points_outside_the_grid() {
+        let drawing = drawing(4, 3);
+        assert_eq!(
+            drawing.resolve(PointRef::At { column: 4, row: 3 }),
+            Ok((180, 120))
+        );
+        assert_eq!(
+            drawing.resolve(PointRef::At { column: 5, row: 1 }),
+            Err(DrawingError::OutOfRange((240, 0)))
+        );
+        assert_eq!(
+            drawing.resolve(PointRef::At { column: 0, row: 1 }),
+            Err(DrawingError::OutOfRange((-60, 0)))
+        );
+        assert_eq!(
+            drawing.resolve(PointRef::At { column: 1, row: 0 }),
+            Err(DrawingError::OutOfRange((0, -60)))
+        );
+        assert!(matches!(
+            drawing.resolve(PointRef::PreviousRow(0)),
+            Err(DrawingError::OutOfRange(_))
+        ));
+    }
+
+    #[test]
+    fn resolve_relative_to_lines() {
+        let mut drawing = drawing(4, 3);
+        assert_eq!(
+            drawing.resolve(PointRef::LastEndpoint),
+            Err(DrawingError::NoLines)
+        );
+        // The previous row doesn't depend on any lines being drawn
+        assert_eq!(drawing.resolve(PointRef::PreviousRow(2)), Ok((60, 60)));
+        drawing.add_line((0, 0), (120, 60)).unwrap();
+        assert_eq!(drawing.resolve(PointRef::LastEndpoint), Ok((120, 60)));
+    }
+
+    #[test]
+    fn add_line_rejects_points_outside_the_grid() {
+        let mut drawing = drawing(4, 3);
+        assert_eq!(
+            drawing.add_line((0, 0), (240, 0)),
+            Err(DrawingError::OutOfRange((240, 0)))
+        );
+        assert_eq!(
+            drawing.add_line((-1, 0), (0, 0)),
+            Err(DrawingError::OutOfRange((-1, 0)))
+        );
+        assert!(drawing.lines().is_empty());
+    }
+
+    #[test]
+    fn add_lines_adds_all_or_none() {
+        let mut drawing = drawing(4, 3);
+        let valid = ((0, 0), (60, 60));
+        assert_eq!(
+            drawing.add_lines(&[valid, ((0, 0), (0, 180))]),
+            Err(DrawingError::OutOfRange((0, 180)))
+        );
+        assert!(drawing.lines().is_empty());
+        drawing.add_lines(&[valid, (valid.1, valid.0)]).unwrap();
+        assert_eq!(drawing.lines(), &[valid, (valid.1, valid.0)]);
+    }
+
+    #[test]
+    fn migrates_drawings_saved_in_cells() {
+        let json = r#"{
+            "grid_width": 4,
+            "grid_height": 3,
+            "includes_points": true,
+            "custom_points": [[2, 1]],
+            "lines": [[[0, 0], [3, 2]], [[1, 1], [2, 1]]]
+        }"#;
+        let drawing: Drawing = serde_json::from_str(json).unwrap();
+        assert_eq!(drawing.layers().len(), 1);
+        assert_eq!(drawing.layers()[0].name, "Layer 1");
+        assert_eq!(drawing.active_layer(), 0);
+        assert_eq!(
+            drawing.lines(),
+            &[((0, 0), (180, 120)), ((60, 60), (120, 60))]
+        );
+        assert_eq!(drawing.custom_points(), &[(120, 60)]);
+    }
+
+    #[test]
+    fn migrates_layers_saved_in_cells() {
+        let mut first = Layer::new("Track".to_string());
+        first.lines = vec![((0, 0), (1, 0))];
+        let mut second = Layer::new("Sidings".to_string());
+        second.lines = vec![((1, 1), (2, 2))];
+        let json = serde_json::json!({
+            "grid_width": 4,
+            "grid_height": 3,
+            "includes_points": false,
+            "layers": [first, second],
+            "active_layer": 5,
+        });
+        let drawing: Drawing = serde_json::from_value(json).unwrap();
+        let layers = drawing.layers();
+        assert_eq!(layers.len(), 2);
+        assert_eq!(layers[0].name, "Track");
+        assert_eq!(layers[0].lines(), &[((0, 0), (60, 0))]);
+        assert_eq!(layers[1].name, "Sidings");
+        assert_eq!(layers[1].lines(), &[((60, 60), (120, 120))]);
+        assert_eq!(drawing.active_layer(), 1);
+
+        // Drawings saved in steps keep their points
+        let saved = serde_json::to_string(&drawing).unwrap();
+        let reloaded: Drawing = serde_json::from_str(&saved).unwrap();
+        assert_eq!(reloaded.layers(), layers);
+    }
+
+    #[test]
+    fn lines_are_those_of_the_active_layer() {
+        let mut drawing = drawing(4, 3);
+        drawing.add_line((0, 0), (60, 0)).unwrap();
+        drawing.add_layer();
+        assert!(drawing.lines().is_empty());
+        drawing.add_line((0, 60), (60, 60)).unwrap();
+        assert_eq!(drawing.lines(), &[((0, 60), (60, 60))]);
+        assert_eq!(drawing.line_count(), 2);
+        drawing.set_active_layer(0);
+        assert_eq!(drawing.lines(), &[((0, 0), (60, 0))]);
+    }
+
+    #[test]
+    fn locked_layers_refuse_edits() {
+        let mut drawing = drawing(4, 3);
+        drawing.add_line((0, 0), (60, 0)).unwrap();
+        drawing.layer_mut(0).unwrap().locked = true;
+        assert_eq!(
+            drawing.add_line((0, 60), (60, 60)),
+            Err(DrawingError::Locked)
+        );
+        assert_eq!(drawing.replace_lines(vec![]), Err(DrawingError::Locked));
+        assert_eq!(
+            drawing.map_points(|(x, y)| (y, x)),
+            Err(DrawingError::Locked)
+        );
+        assert_eq!(drawing.lines(), &[((0, 0), (60, 0))]);
+
+        // Other layers can still be drawn on
+        drawing.add_layer();
+        drawing.add_line((0, 60), (60, 60)).unwrap();
+        assert_eq!(drawing.layers()[0].lines(), &[((0, 0), (60, 0))]);
+    }
+
+    #[test]
+    fn locked_layers_refuse_grid_resizes_that_drop_lines() {
+        let mut drawing = drawing(4, 3);
+        drawing.add_line((0, 120), (180, 120)).unwrap();
+        drawing.layer_mut(0).unwrap().locked = true;
+        assert_eq!(drawing.set_grid_width(3), Err(DrawingError::Locked));
+        assert_eq!(drawing.remove_row(), Err(DrawingError::Locked));
+        assert_eq!((drawing.grid_width, drawing.grid_height), (4, 3));
+        assert_eq!(drawing.lines(), &[((0, 120), (180, 120))]);
+
+        // Growing the grid keeps every line
+        drawing.set_grid_width(5).unwrap();
+        drawing.add_row();
+        assert_eq!((drawing.grid_width, drawing.grid_height), (5, 4));
+
+        drawing.layer_mut(0).unwrap().locked = false;
+        drawing.remove_row().unwrap();
+        drawing.remove_row().unwrap();
+        assert!(drawing.lines().is_empty());
+    }
+
+    #[test]
+    fn fitted_canvas_has_a_size_without_margin() {
+        let mut drawing = drawing(1, 1);
+        drawing.set_margin(0_f64);
+        assert!(drawing.view_width() > 0_f64 && drawing.view_height() > 0_f64);
+        let size = crate::export::PhysicalSize::new(&drawing, 200_f64);
+        assert!(size.scale.is_finite() && size.height_mm.is_finite());
+        let (x, y) = size.to_mm(drawing.position((0, 0)));
+        assert!(x.is_finite() && y.is_finite());
+    }
+
+    #[test]
+    fn resolves_large_columns_and_rows_as_out_of_range() {
+        let drawing = drawing(4, 3);
+        for (column, row) in [(1094, 1), (1, 1094), (547, 548), (u16::MAX, u16::MAX)] {
+            assert_eq!(
+                drawing.resolve(PointRef::At { column, row }),
+                Err(DrawingError::OutOfRange((
+                    (column as i32 - 1) * CELL_STEPS as i32,
+                    (row as i32 - 1) * CELL_STEPS as i32
+                )))
+            );
         }
-        ret
+        assert!(matches!(
+            drawing.resolve(PointRef::PreviousRow(u16::MAX)),
+            Err(DrawingError::OutOfRange(_))
+        ));
+        assert_eq!(
+            DrawingError::OutOfRange((1093 * CELL_STEPS as i32, 0)).to_string(),
+            "Point 1094,1 is outside of the grid"
+        );
     }
 }
@@ -0,0 +1,238 @@
+pub mod dxf;
+pub mod outline;
+pub mod pdf;
+pub mod plotter;
+pub mod png;
+pub mod svg;
+pub mod zip;
+
+use serde::{Deserialize, Serialize};
+
+use crate::drawing::Drawing;
+
+/// Characters that aren't allowed in filenames on at least one common platform
+const FILENAME_RESERVED: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+const DEFAULT_FILENAME: &str = "Track";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum Format {
+    #[default]
+    Svg,
+    Png,
+    Pdf,
+    Gcode,
+    Hpgl,
+    Dxf,
+    /// SVG of the outlined strokes, for cutting
+    OutlineSvg,
+}
+
+impl Format {
+    pub const ALL: [Format; 7] = [
+        Format::Svg,
+        Format::Png,
+        Format::Pdf,
+        Format::Gcode,
+        Format::Hpgl,
+        Format::Dxf,
+        Format::OutlineSvg,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Svg => "SVG",
+            Format::Png => "PNG",
+            Format::Pdf => "PDF",
+            Format::Gcode => "G-code",
+            Format::Hpgl => "HPGL",
+            Format::Dxf => "DXF",
+            Format::OutlineSvg => "Outline SVG",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Svg | Format::OutlineSvg => "svg",
+            Format::Png => "png",
+            Format::Pdf => "pdf",
+            Format::Gcode => "gcode",
+            Format::Hpgl => "hpgl",
+            Format::Dxf => "dxf",
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            Format::Svg | Format::OutlineSvg => "image/svg+xml",
+            Format::Png => "image/png",
+            Format::Pdf => "application/pdf",
+            Format::Gcode | Format::Hpgl => "text/plain",
+            Format::Dxf => "application/dxf",
+        }
+    }
+}
+
+/// Values available to a filename template
+pub struct FilenameFields<'a> {
+    pub name: &'a str,
+    pub seed: Option<u64>,
+    /// The date as `YYYY-MM-DD`
+    pub date: &'a str,
+    /// The exported size with its units, such as `2000x1500px` or `200x150mm`
+    pub size: String,
+}
+
+/// Fills in a filename template, replacing `{name}`, `{seed}`, `{date}`, `{grid}` (the grid
+/// size as `columns x rows`) & `{size}`, and adds the format's extension
+pub fn filename(
+    template: &str,
+    fields: &FilenameFields,
+    drawing: &Drawing,
+    format: Format,
+) -> String {
+    let expanded = template
+        .replace("{name}", fields.name)
+        .replace(
+            "{seed}",
+            &fields.seed.map(|seed| seed.to_string()).unwrap_or_default(),
+        )
+        .replace("{date}", fields.date)
+        .replace("{size}", &fields.size)
+        .replace(
+            "{grid}",
+            &format!("{}x{}", drawing.grid_width, drawing.grid_height),
+        );
+    let cleaned: String = expanded
+        .chars()
+        .map(|c| {
+            if FILENAME_RESERVED.contains(&c) || c.is_control() {
+                '-'
+            } else {
+                c
+            }
+        })
+        .collect();
+    // Empty fields can leave separators dangling at either end
+    let trimmed =
+        cleaned.trim_matches(|c: char| c.is_whitespace() || c == '-' || c == '_' || c == '.');
+    let stem = if trimmed.is_empty() {
+        DEFAULT_FILENAME
+    } else {
+        trimmed
+    };
+    format!("{}.{}", stem, format.extension())
+}
+
+/// Maps a drawing onto paper of a real world width, with the origin at the bottom left as
+/// plotters & CAD tools expect
+#[derive(Clone, Copy, Debug)]
+pub struct PhysicalSize {
+    pub width_mm: f64,
+    pub height_mm: f64,
+    /// Millimetres per view box unit
+    pub scale: f64,
+}
+
+impl PhysicalSize {
+    pub fn new(drawing: &Drawing, width_mm: f64) -> PhysicalSize {
+        let scale = width_mm / drawing.view_width();
+        PhysicalSize {
+            width_mm,
+            height_mm: drawing.view_height() * scale,
+            scale,
+        }
+    }
+
+    /// Converts a position in view box units to millimetres
+    pub fn to_mm(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        (x * self.scale, self.height_mm - y * self.scale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields<'a>(name: &'a str, seed: Option<u64>, date: &'a str) -> FilenameFields<'a> {
+        FilenameFields {
+            name,
+            seed,
+            date,
+            size: "200x150mm".to_string(),
+        }
+    }
+
+    fn drawing() -> Drawing {
+        let mut drawing = Drawing::new();
+        drawing.set_grid_width(8).unwrap();
+        drawing.set_grid_height(6).unwrap();
+        drawing
+    }
+
+    #[test]
+    fn fills_in_each_field() {
+        let fields = fields("Loop", Some(42), "2024-03-01");
+        let drawing = drawing();
+        let cases = [
+            ("{name}", "Loop.svg"),
+            ("{seed}", "42.svg"),
+            ("{date}", "2024-03-01.svg"),
+            ("{grid}", "8x6.svg"),
+            ("{size}", "200x150mm.svg"),
+            ("{name}_{grid}_{seed}", "Loop_8x6_42.svg"),
+            ("plain", "plain.svg"),
+        ];
+        for (template, expected) in cases {
+            assert_eq!(filename(template, &fields, &drawing, Format::Svg), expected);
+        }
+        assert_eq!(
+            filename("{name}", &fields, &drawing, Format::Gcode),
+            "Loop.gcode"
+        );
+    }
+
+    #[test]
+    fn trims_separators_left_by_empty_fields() {
+        let fields = fields("", None, "2024-03-01");
+        let drawing = drawing();
+        assert_eq!(
+            filename("{seed}-{name}_{date}", &fields, &drawing, Format::Png),
+            "2024-03-01.png"
+        );
+        assert_eq!(
+            filename("{date}_{name}-{seed}", &fields, &drawing, Format::Png),
+            "2024-03-01.png"
+        );
+        assert_eq!(
+            filename(" {name}.{seed} ", &fields, &drawing, Format::Png),
+            "Track.png"
+        );
+    }
+
+    #[test]
+    fn replaces_reserved_characters() {
+        let fields = fields("a/b\\c:d*e?f\"g<h>i|j\tk", None, "");
+        assert_eq!(
+            filename("{name}", &fields, &drawing(), Format::Pdf),
+            "a-b-c-d-e-f-g-h-i-j-k.pdf"
+        );
+        assert_eq!(
+            filename("/{name}/", &fields, &drawing(), Format::Pdf),
+            "a-b-c-d-e-f-g-h-i-j-k.pdf"
+        );
+    }
+
+    #[test]
+    fn falls_back_to_a_default_name() {
+        let fields = fields("", None, "");
+        assert_eq!(filename("", &fields, &drawing(), Format::Dxf), "Track.dxf");
+        assert_eq!(
+            filename("{name}_{seed}", &fields, &drawing(), Format::Dxf),
+            "Track.dxf"
+        );
+        assert_eq!(
+            filename("???", &fields, &drawing(), Format::Dxf),
+            "Track.dxf"
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use std::fmt::Write;
+
+use crate::drawing::{Drawing, POINT_RADIUS};
+use crate::export::{outline, PhysicalSize};
+
+pub const LINES_LAYER: &str = "TRACKS";
+pub const POINTS_LAYER: &str = "POINTS";
+pub const OUTLINE_LAYER: &str = "OUTLINE";
+pub const FRAME_LAYER: &str = "FRAME";
+/// `$INSUNITS` value for millimetres
+const MILLIMETRES: u8 = 4;
+/// `$MEASUREMENT` value for metric
+const METRIC: u8 = 1;
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// Width of the drawing, the height follows from the drawing's aspect ratio
+    pub width_mm: f64,
+    /// Add each grid point as a circle on its own layer
+    pub include_points: bool,
+    /// Replace the lines & points with closed outlines of their strokes, for cutting
+    pub outline: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            width_mm: 200_f64,
+            include_points: false,
+            outline: false,
+        }
+    }
+}
+
+/// Writes a drawing as an R12 DXF file in millimetres, with each line a LINE entity, or a
+/// POLYLINE where a transit map bends it. The units headers came after R12, which readers of
+/// R12 ignore, but later ones use to scale the drawing
+pub fn render(drawing: &Drawing, options: &Options) -> String {
+    let size = PhysicalSize::new(drawing, options.width_mm);
+    let mut dxf = Dxf::default();
+
+    dxf.pair(0, "SECTION");
+    dxf.pair(2, "HEADER");
+    dxf.pair(9, "$ACADVER");
+    dxf.pair(1, "AC1009");
+    dxf.pair(9, "$INSUNITS");
+    dxf.pair(70, MILLIMETRES);
+    dxf.pair(9, "$MEASUREMENT");
+    dxf.pair(70, METRIC);
+    dxf.pair(9, "$EXTMIN");
+    dxf.point(0, (0_f64, 0_f64));
+    dxf.pair(9, "$EXTMAX");
+    dxf.point(0, (size.width_mm, size.height_mm));
+    dxf.pair(0, "ENDSEC");
+
+    let frame = drawing.frame_points();
+    let mut layers = if options.outline {
+        vec![OUTLINE_LAYER]
+    } else if options.include_points {
+        vec![LINES_LAYER, POINTS_LAYER]
+    } else {
+        vec![LINES_LAYER]
+    };
+    if frame.is_some() && !options.outline {
+        layers.push(FRAME_LAYER);
+    }
+    dxf.pair(0, "SECTION");
+    dxf.pair(2, "TABLES");
+    dxf.pair(0, "TABLE");
+    dxf.pair(2, "LTYPE");
+    dxf.pair(70, 1);
+    dxf.pair(0, "LTYPE");
+    dxf.pair(2, "CONTINUOUS");
+    dxf.pair(70, 0);
+    dxf.pair(3, "Solid line");
+    dxf.pair(72, 65);
+    dxf.pair(73, 0);
+    dxf.pair(40, 0);
+    dxf.pair(0, "ENDTAB");
+    dxf.pair(0, "TABLE");
+    dxf.pair(2, "LAYER");
+    dxf.pair(70, layers.len());
+    for layer in &layers {
+        dxf.pair(0, "LAYER");
+        dxf.pair(2, layer);
+        dxf.pair(70, 0);
+        dxf.pair(62, 7);
+        dxf.pair(6, "CONTINUOUS");
+    }
+    dxf.pair(0, "ENDTAB");
+    dxf.pair(0, "ENDSEC");
+
+    dxf.pair(0, "SECTION");
+    dxf.pair(2, "ENTITIES");
+    if options.outline {
+        let scene = drawing.machine_scene(options.include_points);
+        for contour in outline::outline(&scene).iter().flatten() {
+            dxf.polyline(
+                OUTLINE_LAYER,
+                contour.iter().map(|position| size.to_mm(*position)),
+                true,
+            );
+        }
+    } else {
+        for path in drawing.machine_paths() {
+            if let [from, to] = path[..] {
+                dxf.pair(0, "LINE");
+                dxf.pair(8, LINES_LAYER);
+                dxf.point(0, size.to_mm(from));
+                dxf.point(1, size.to_mm(to));
+            } else {
+                dxf.polyline(
+                    LINES_LAYER,
+                    path.iter().map(|position| size.to_mm(*position)),
+                    false,
+                );
+            }
+        }
+    }
+    if let Some(points) = frame.filter(|_| !options.outline) {
+        dxf.polyline(
+            FRAME_LAYER,
+            points.iter().map(|position| size.to_mm(*position)),
+            true,
+        );
+    }
+    if options.include_points && !options.outline {
+        for point in drawing.marked_points() {
+            dxf.pair(0, "CIRCLE");
+            dxf.pair(8, POINTS_LAYER);
+            dxf.point(0, size.to_mm(drawing.position(point)));
+            dxf.pair(40, POINT_RADIUS * size.scale);
+        }
+    }
+    dxf.pair(0, "ENDSEC");
+    dxf.pair(0, "EOF");
+    dxf.out
+}
+
+#[derive(Default)]
+struct Dxf {
+    out: String,
+}
+
+impl Dxf {
+    fn pair(&mut self, code: u16, value: impl std::fmt::Display) {
+        writeln!(self.out, "{}\n{}", code, value).unwrap();
+    }
+
+    /// Writes a 2D point, `offset` picking which of the entity's points it is
+    fn point(&mut self, offset: u16, (x, y): (f64, f64)) {
+        self.pair(10 + offset, format!("{:.4}", x));
+        self.pair(20 + offset, format!("{:.4}", y));
+        self.pair(30 + offset, 0);
+    }
+
+    /// Writes a POLYLINE through the given points, joining the last back to the first if closed
+    fn polyline(&mut self, layer: &str, points: impl Iterator<Item = (f64, f64)>, closed: bool) {
+        self.pair(0, "POLYLINE");
+        self.pair(8, layer);
+        self.pair(66, 1);
+        self.pair(70, u8::from(closed));
+        self.point(0, (0_f64, 0_f64));
+        for point in points {
+            self.pair(0, "VERTEX");
+            self.pair(8, layer);
+            self.point(0, point);
+        }
+        self.pair(0, "SEQEND");
+        self.pair(8, layer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_sets_millimetres() {
+        let dxf = render(&Drawing::new(), &Options::default());
+        let header = &dxf[..dxf.find("ENDSEC").unwrap()];
+        assert!(header.contains("9\n$INSUNITS\n70\n4\n"));
+        assert!(header.contains("9\n$MEASUREMENT\n70\n1\n"));
+    }
+}
@@ -0,0 +1,157 @@
+use std::f64::consts::PI;
+use std::fmt::Write;
+
+use i_overlay::core::fill_rule::FillRule;
+use i_overlay::float::simplify::SimplifyShape;
+
+use crate::render::{Scene, Shape};
+
+/// Number of straight edges used to approximate each circle
+const CIRCLE_SEGMENTS: usize = 32;
+
+/// A closed loop of positions, the first not repeated at the end
+pub type Contour = Vec<(f64, f64)>;
+
+/// Outer contour of an area, followed by the contours of any holes in it
+pub type Region = Vec<Contour>;
+
+/// Converts the stroked lines & dots of a scene to filled areas, merging any that overlap so
+/// each region can be cut or engraved as a single closed outline
+pub fn outline(scene: &Scene) -> Vec<Region> {
+    let mut contours: Vec<Vec<[f64; 2]>> = Vec::new();
+    add_contours(&mut contours, &scene.shapes);
+
+    // Every contour winds the same way, so the non-zero rule fills wherever any shape does
+    contours
+        .simplify_shape(FillRule::NonZero)
+        .into_iter()
+        .map(|region| {
+            region
+                .into_iter()
+                .map(|contour| contour.into_iter().map(|[x, y]| (x, y)).collect())
+                .collect()
+        })
+        .collect()
+}
+
+fn add_contours(contours: &mut Vec<Vec<[f64; 2]>>, shapes: &[Shape]) {
+    for shape in shapes {
+        match shape {
+            Shape::Line { from, to, width } => {
+                contours.extend(line_contour(*from, *to, width / 2_f64));
+            }
+            Shape::Dot { centre, radius } => contours.push(circle_contour(*centre, *radius)),
+            Shape::Dots { centres, radius } => contours.extend(
+                centres
+                    .iter()
+                    .map(|centre| circle_contour(*centre, *radius)),
+            ),
+            // Only the strokes are cut, not the background
+            Shape::Fill { .. } => {}
+            Shape::Polygon { points, width } => {
+                for (idx, from) in points.iter().enumerate() {
+                    let to = points[(idx + 1) % points.len()];
+                    contours.extend(line_contour(*from, to, width / 2_f64));
+                    // Round the joins, so the sides meet without notches
+                    contours.push(circle_contour(*from, width / 2_f64));
+                }
+            }
+            Shape::Area { points } => contours.push(counter_clockwise(
+                points.iter().map(|(x, y)| [*x, *y]).collect(),
+            )),
+            Shape::Path { points, width } => {
+                for pair in points.windows(2) {
+                    contours.extend(line_contour(pair[0], pair[1], width / 2_f64));
+                }
+                let joins = points.iter().skip(1).take(points.len().saturating_sub(2));
+                contours.extend(joins.map(|join| circle_contour(*join, width / 2_f64)));
+            }
+            Shape::Square { centre, size } => contours.push(counter_clockwise(vec![
+                [centre.0 - size, centre.1 - size],
+                [centre.0 + size, centre.1 - size],
+                [centre.0 + size, centre.1 + size],
+                [centre.0 - size, centre.1 + size],
+            ])),
+            Shape::Ring {
+                centre,
+                radius,
+                width,
+            } => {
+                contours.push(circle_contour(*centre, radius + width / 2_f64));
+                // Winding the inner circle the other way leaves a hole in the middle
+                if *radius > width / 2_f64 {
+                    let mut hole = circle_contour(*centre, radius - width / 2_f64);
+                    hole.reverse();
+                    contours.push(hole);
+                }
+            }
+            Shape::Group { shapes, .. } => add_contours(contours, shapes),
+        }
+    }
+}
+
+/// Rectangle covered by a line with butt ends, `None` if the line has no length
+fn line_contour(from: (f64, f64), to: (f64, f64), half_width: f64) -> Option<Vec<[f64; 2]>> {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let length = dx.hypot(dy);
+    if length == 0_f64 || half_width <= 0_f64 {
+        return None;
+    }
+    let (nx, ny) = (-dy / length * half_width, dx / length * half_width);
+    Some(counter_clockwise(vec![
+        [from.0 + nx, from.1 + ny],
+        [to.0 + nx, to.1 + ny],
+        [to.0 - nx, to.1 - ny],
+        [from.0 - nx, from.1 - ny],
+    ]))
+}
+
+fn circle_contour((x, y): (f64, f64), radius: f64) -> Vec<[f64; 2]> {
+    counter_clockwise(
+        (0..CIRCLE_SEGMENTS)
+            .map(|idx| {
+                let angle = 2_f64 * PI * idx as f64 / CIRCLE_SEGMENTS as f64;
+                [x + radius * angle.cos(), y + radius * angle.sin()]
+            })
+            .collect(),
+    )
+}
+
+fn counter_clockwise(mut contour: Vec<[f64; 2]>) -> Vec<[f64; 2]> {
+    let area: f64 = contour
+        .iter()
+        .zip(contour.iter().cycle().skip(1))
+        .map(|([x1, y1], [x2, y2])| x1 * y2 - x2 * y1)
+        .sum();
+    if area < 0_f64 {
+        contour.reverse();
+    }
+    contour
+}
+
+/// Writes the outline of a scene as an SVG of filled paths
+pub fn svg(scene: &Scene) -> String {
+    let mut path = String::new();
+    for contour in outline(scene).iter().flatten() {
+        for (idx, (x, y)) in contour.iter().enumerate() {
+            write!(
+                path,
+                "{}{:.3} {:.3}",
+                if idx == 0 { "M" } else { "L" },
+                x,
+                y
+            )
+            .unwrap();
+        }
+        path.push('Z');
+    }
+    format!(
+        concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {w} {h}" width="{w}" height="{h}">"#,
+            r#"<path fill="black" fill-rule="evenodd" d="{d}"/></svg>"#
+        ),
+        w = scene.width,
+        h = scene.height,
+        d = path
+    )
+}
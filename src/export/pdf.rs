@@ -0,0 +1,499 @@
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::drawing::Drawing;
+use crate::render::{Scene, Shape};
+
+/// PDF user space units (points) per millimetre
+const POINTS_PER_MM: f64 = 72_f64 / 25.4_f64;
+const CAPTION_SIZE: f64 = 10_f64;
+/// Space reserved under each drawing for its caption, in points
+const CAPTION_HEIGHT: f64 = 2.5_f64 * CAPTION_SIZE;
+/// Space between drawings laid out on the same page, in millimetres
+const GUTTER_MM: f64 = 5_f64;
+/// Horizontal bands a background gradient is drawn with
+const GRADIENT_BANDS: usize = 64;
+/// Control point distance for approximating a quarter circle with a cubic bézier
+const KAPPA: f64 = 0.552_284_75_f64;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum PaperSize {
+    A3,
+    A4,
+    A5,
+    Letter,
+}
+
+impl PaperSize {
+    pub const ALL: [PaperSize; 4] = [
+        PaperSize::A3,
+        PaperSize::A4,
+        PaperSize::A5,
+        PaperSize::Letter,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PaperSize::A3 => "A3",
+            PaperSize::A4 => "A4",
+            PaperSize::A5 => "A5",
+            PaperSize::Letter => "Letter",
+        }
+    }
+
+    /// Portrait width & height in millimetres
+    pub fn size_mm(&self) -> (f64, f64) {
+        match self {
+            PaperSize::A3 => (297_f64, 420_f64),
+            PaperSize::A4 => (210_f64, 297_f64),
+            PaperSize::A5 => (148_f64, 210_f64),
+            PaperSize::Letter => (215.9_f64, 279.4_f64),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub paper: PaperSize,
+    pub landscape: bool,
+    pub margin_mm: f64,
+    /// Number of drawings across & down each page
+    pub columns: u16,
+    pub rows: u16,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            paper: PaperSize::A4,
+            landscape: false,
+            margin_mm: 15_f64,
+            columns: 1,
+            rows: 1,
+        }
+    }
+}
+
+impl Options {
+    fn page_size(&self) -> (f64, f64) {
+        let (width, height) = self.paper.size_mm();
+        let (width, height) = (width * POINTS_PER_MM, height * POINTS_PER_MM);
+        if self.landscape {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+}
+
+/// A caption naming a drawing, with the seed it was drawn from & the date it's printed
+pub fn caption(drawing: &Drawing, date: &str) -> String {
+    let seed = drawing.seed.map(|seed| format!("seed {}", seed));
+    [Some(drawing.title.trim()), seed.as_deref(), Some(date)]
+        .iter()
+        .flatten()
+        .copied()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" - ")
+}
+
+/// Lays out scenes, each with an optional caption, `columns` x `rows` to a page
+pub fn render(drawings: &[(Scene, Option<String>)], options: &Options) -> Vec<u8> {
+    let (page_width, page_height) = options.page_size();
+    let columns = options.columns.max(1) as usize;
+    let rows = options.rows.max(1) as usize;
+    let margin = options.margin_mm * POINTS_PER_MM;
+    let gutter = GUTTER_MM * POINTS_PER_MM;
+    let cell_width = (page_width - 2_f64 * margin - gutter * (columns - 1) as f64) / columns as f64;
+    let cell_height = (page_height - 2_f64 * margin - gutter * (rows - 1) as f64) / rows as f64;
+
+    let pages: Vec<String> = drawings
+        .chunks(columns * rows)
+        .map(|page| {
+            let mut content = String::new();
+            for (idx, (scene, caption)) in page.iter().enumerate() {
+                let left = margin + (idx % columns) as f64 * (cell_width + gutter);
+                let top = page_height - margin - (idx / columns) as f64 * (cell_height + gutter);
+                let drawing_height = if caption.is_some() {
+                    cell_height - CAPTION_HEIGHT
+                } else {
+                    cell_height
+                };
+                draw_scene(&mut content, scene, left, top, cell_width, drawing_height);
+                if let Some(caption) = caption {
+                    draw_caption(
+                        &mut content,
+                        caption,
+                        left,
+                        top - cell_height + CAPTION_SIZE / 2_f64,
+                    );
+                }
+            }
+            content
+        })
+        .collect();
+
+    write_document(&pages, page_width, page_height)
+}
+
+/// Draws a scene scaled to fit within a box, whose top left corner is at `left`, `top`
+fn draw_scene(content: &mut String, scene: &Scene, left: f64, top: f64, width: f64, height: f64) {
+    if width <= 0_f64 || height <= 0_f64 {
+        return;
+    }
+    let scale = (width / scene.width).min(height / scene.height);
+    let x = left + (width - scene.width * scale) / 2_f64;
+    let y = top - (height - scene.height * scale) / 2_f64;
+
+    // Flip the y axis so the scene can be drawn in its own top-down coordinates
+    writeln!(content, "q {} 0 0 {} {} {} cm", scale, -scale, x, y).unwrap();
+    draw_shapes(content, &scene.shapes, (scene.width, scene.height));
+    content.push_str("Q\n");
+}
+
+/// Draws shapes in scene coordinates, `size` being the scene's width & height
+fn draw_shapes(content: &mut String, shapes: &[Shape], size: (f64, f64)) {
+    for shape in shapes {
+        match shape {
+            Shape::Line { from, to, width } => {
+                writeln!(
+                    content,
+                    "{} w {} {} m {} {} l S",
+                    width, from.0, from.1, to.0, to.1
+                )
+                .unwrap();
+            }
+            Shape::Dot { centre, radius } => {
+                circle(content, *centre, *radius);
+                content.push_str("f\n");
+            }
+            Shape::Dots { centres, radius } => {
+                centres
+                    .iter()
+                    .for_each(|centre| circle(content, *centre, *radius));
+                content.push_str("f\n");
+            }
+            Shape::Fill { top, bottom } => {
+                // Gradients are drawn as thin bands, saving on a shading dictionary
+                let bands = if top == bottom { 1 } else { GRADIENT_BANDS };
+                let band_height = size.1 / bands as f64;
+                content.push_str("q\n");
+                for band in 0..bands {
+                    let t = (band as f64 + 0.5_f64) / bands as f64;
+                    let channel =
+                        |from: u8, to: u8| (from as f64 + (to as f64 - from as f64) * t) / 255_f64;
+                    writeln!(
+                        content,
+                        "{} {} {} rg 0 {} {} {} re f",
+                        channel(top.r, bottom.r),
+                        channel(top.g, bottom.g),
+                        channel(top.b, bottom.b),
+                        band as f64 * band_height,
+                        size.0,
+                        // Overlap the next band so no gaps show between them
+                        band_height * 1.01_f64
+                    )
+                    .unwrap();
+                }
+                content.push_str("Q\n");
+            }
+            Shape::Area { points } => {
+                for (idx, (x, y)) in points.iter().enumerate() {
+                    let op = if idx == 0 { "m" } else { "l" };
+                    write!(content, "{} {} {} ", x, y, op).unwrap();
+                }
+                content.push_str("f\n");
+            }
+            Shape::Polygon { points, width } | Shape::Path { points, width } => {
+                write!(content, "{} w", width).unwrap();
+                for (idx, (x, y)) in points.iter().enumerate() {
+                    let op = if idx == 0 { "m" } else { "l" };
+                    write!(content, " {} {} {}", x, y, op).unwrap();
+                }
+                // Close & stroke a polygon, only stroke a path
+                if let Shape::Polygon { .. } = shape {
+                    content.push_str(" s\n");
+                } else {
+                    content.push_str(" S\n");
+                }
+            }
+            Shape::Square { centre, size } => {
+                writeln!(
+                    content,
+                    "{} {} {} {} re f",
+                    centre.0 - size,
+                    centre.1 - size,
+                    2_f64 * size,
+                    2_f64 * size
+                )
+                .unwrap();
+            }
+            Shape::Ring {
+                centre,
+                radius,
+                width,
+            } => {
+                writeln!(content, "{} w", width).unwrap();
+                circle(content, *centre, *radius);
+                content.push_str("S\n");
+            }
+            Shape::Group { colour, shapes, .. } => {
+                let (r, g, b) = (
+                    colour.r as f64 / 255_f64,
+                    colour.g as f64 / 255_f64,
+                    colour.b as f64 / 255_f64,
+                );
+                // Save the graphics state so the colour only applies within the group
+                writeln!(
+                    content,
+                    "q {r} {g} {b} rg {r} {g} {b} RG",
+                    r = r,
+                    g = g,
+                    b = b
+                )
+                .unwrap();
+                draw_shapes(content, shapes, size);
+                content.push_str("Q\n");
+            }
+        }
+    }
+}
+
+fn circle(content: &mut String, (x, y): (f64, f64), r: f64) {
+    let k = r * KAPPA;
+    // Control points & end point of each quarter, relative to the centre
+    let quarters = [
+        [(r, k), (k, r), (0_f64, r)],
+        [(-k, r), (-r, k), (-r, 0_f64)],
+        [(-r, -k), (-k, -r), (0_f64, -r)],
+        [(k, -r), (r, -k), (r, 0_f64)],
+    ];
+    writeln!(content, "{} {} m", x + r, y).unwrap();
+    for [(x1, y1), (x2, y2), (x3, y3)] in quarters.iter() {
+        writeln!(
+            content,
+            "{} {} {} {} {} {} c",
+            x + x1,
+            y + y1,
+            x + x2,
+            y + y2,
+            x + x3,
+            y + y3
+        )
+        .unwrap();
+    }
+}
+
+fn draw_caption(content: &mut String, caption: &str, x: f64, y: f64) {
+    writeln!(
+        content,
+        "BT /F1 {} Tf {} {} Td ({}) Tj ET",
+        CAPTION_SIZE,
+        x,
+        y,
+        escape_text(caption)
+    )
+    .unwrap();
+}
+
+/// Escapes a string for a PDF literal, replacing anything the standard fonts can't show
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            _ => escaped.push('?'),
+        }
+    }
+    escaped
+}
+
+/// Writes the PDF file structure around each page's content stream
+fn write_document(pages: &[String], page_width: f64, page_height: f64) -> Vec<u8> {
+    // Objects are numbered from 1: the catalog, page tree & font, then a page & its contents per page
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            (0..pages.len())
+                .map(|idx| format!("{} 0 R", 4 + 2 * idx))
+                .collect::<Vec<_>>()
+                .join(" "),
+            pages.len()
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    ];
+    for (idx, content) in pages.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            page_width,
+            page_height,
+            5 + 2 * idx
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ));
+    }
+
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::new();
+    for (idx, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        write!(pdf, "{} 0 obj\n{}\nendobj\n", idx + 1, object).unwrap();
+    }
+    let xref = pdf.len();
+    write!(pdf, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).unwrap();
+    for offset in offsets {
+        writeln!(pdf, "{:010} 00000 n ", offset).unwrap();
+    }
+    write!(
+        pdf,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    )
+    .unwrap();
+    pdf.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scene() -> Scene {
+        Scene {
+            width: 100_f64,
+            height: 100_f64,
+            shapes: vec![],
+        }
+    }
+
+    fn text(pdf: &[u8]) -> &str {
+        std::str::from_utf8(pdf).unwrap()
+    }
+
+    /// The content stream of each page, in order
+    fn page_contents(pdf: &str) -> Vec<&str> {
+        pdf.split(">>\nstream\n")
+            .skip(1)
+            .map(|stream| stream.split("endstream").next().unwrap())
+            .collect()
+    }
+
+    /// The scale & bottom left corner of each scene drawn in a content stream
+    fn placements(content: &str) -> Vec<(f64, f64, f64)> {
+        content
+            .lines()
+            .filter(|line| line.starts_with("q ") && line.ends_with(" cm"))
+            .map(|line| {
+                let numbers: Vec<f64> = line[2..line.len() - 3]
+                    .split(' ')
+                    .map(|number| number.parse().unwrap())
+                    .collect();
+                (numbers[0], numbers[4], numbers[5])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn captions_name_seed_and_date() {
+        let mut drawing = Drawing::new();
+        assert_eq!(caption(&drawing, "2024-03-01"), "2024-03-01");
+        drawing.title = "Loop".to_string();
+        drawing.seed = Some(42);
+        assert_eq!(
+            caption(&drawing, "2024-03-01"),
+            "Loop - seed 42 - 2024-03-01"
+        );
+        assert_eq!(caption(&drawing, ""), "Loop - seed 42");
+    }
+
+    #[test]
+    fn xref_points_at_each_object() {
+        let drawings = vec![(scene(), Some("One (1)".to_string())), (scene(), None)];
+        let pdf = render(&drawings, &Options::default());
+        let pdf = text(&pdf);
+        assert!(pdf.starts_with("%PDF-1.4\n"));
+        assert!(pdf.ends_with("%%EOF\n"));
+
+        let startxref: usize = pdf
+            .rsplit("startxref\n")
+            .next()
+            .unwrap()
+            .lines()
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap();
+        let xref = &pdf[startxref..];
+        let mut lines = xref.lines();
+        assert_eq!(lines.next(), Some("xref"));
+        // The catalog, page tree & font, then a page & its contents for each drawing
+        assert_eq!(lines.next(), Some("0 8"));
+        assert_eq!(lines.next(), Some("0000000000 65535 f "));
+        for number in 1..8 {
+            let entry = lines.next().unwrap();
+            assert_eq!(entry.len(), 19);
+            assert!(entry.ends_with(" 00000 n "));
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj\n", number)));
+        }
+        assert_eq!(lines.next(), Some("trailer"));
+        assert!(xref.contains("/Size 8 /Root 1 0 R"));
+
+        for content in page_contents(pdf) {
+            assert!(pdf.contains(&format!("<< /Length {} >>\nstream\n", content.len())));
+        }
+    }
+
+    #[test]
+    fn lays_out_drawings_across_pages() {
+        let options = Options {
+            columns: 2,
+            rows: 2,
+            margin_mm: 10_f64,
+            ..Options::default()
+        };
+        let drawings: Vec<(Scene, Option<String>)> = (0..5)
+            .map(|idx| (scene(), Some(format!("Drawing {}", idx))))
+            .collect();
+        let pdf = render(&drawings, &options);
+        let pdf = text(&pdf);
+        assert!(pdf.contains("/Kids [4 0 R 6 0 R] /Count 2"));
+
+        let pages = page_contents(pdf);
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].matches(" Tj ET").count(), 4);
+        assert_eq!(pages[1].matches(" Tj ET").count(), 1);
+
+        let placed = placements(pages[0]);
+        assert_eq!(placed.len(), 4);
+        let (page_width, page_height) = options.page_size();
+        // Allowing for rounding where a drawing fills its cell
+        let margin = options.margin_mm * POINTS_PER_MM - 1e-6_f64;
+        for (scale, x, y) in &placed {
+            let size = scene().width * scale;
+            assert!(*x >= margin && x + size <= page_width - margin);
+            // Flipped, so `y` is the top of the scene
+            assert!(y - size >= margin && *y <= page_height - margin);
+        }
+        // Left to right, then top to bottom
+        assert_eq!(placed[0].1, placed[2].1);
+        assert_eq!(placed[1].1, placed[3].1);
+        assert!(placed[0].1 < placed[1].1);
+        assert_eq!(placed[0].2, placed[1].2);
+        assert_eq!(placed[2].2, placed[3].2);
+        assert!(placed[0].2 > placed[2].2);
+        // The last drawing starts the next page in the first cell
+        assert_eq!(placements(pages[1]), placed[..1]);
+    }
+}
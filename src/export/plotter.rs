@@ -0,0 +1,376 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::drawing::Drawing;
+use crate::export::PhysicalSize;
+
+/// A position on the paper in millimetres, with the origin at the bottom left
+pub type Position = (f64, f64);
+
+/// Positions closer than this, in millimetres, are treated as the same point
+const TOLERANCE_MM: f64 = 0.01;
+/// Time taken to raise or lower the pen
+const PEN_MOVE_SECONDS: f64 = 0.2;
+/// HPGL plotter units per millimetre
+const HPGL_UNITS_PER_MM: f64 = 40_f64;
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// Width of the plot, the height follows from the drawing's aspect ratio
+    pub width_mm: f64,
+    /// Speed while drawing in millimetres per minute
+    pub draw_speed: f64,
+    /// Speed while moving with the pen up in millimetres per minute
+    pub travel_speed: f64,
+    /// G-code commands to raise & lower the pen
+    pub pen_up: String,
+    pub pen_down: String,
+    /// Mark each grid point with a dot of the pen
+    pub include_points: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            width_mm: 200_f64,
+            draw_speed: 1500_f64,
+            travel_speed: 4000_f64,
+            pen_up: "G0 Z5".to_string(),
+            pen_down: "G0 Z0".to_string(),
+            include_points: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Stats {
+    pub draw_mm: f64,
+    pub travel_mm: f64,
+    pub pen_lifts: usize,
+    pub seconds: f64,
+}
+
+/// The order a drawing is plotted in
+#[derive(Clone, Debug)]
+pub struct Plot {
+    pub width_mm: f64,
+    pub height_mm: f64,
+    /// Paths drawn with the pen down, a path of a single position being a dot
+    pub paths: Vec<Vec<Position>>,
+    pub stats: Stats,
+}
+
+/// Converts a drawing to pen paths, ordered & joined up to keep pen up travel short
+pub fn plan(drawing: &Drawing, options: &Options) -> Plot {
+    let size = PhysicalSize::new(drawing, options.width_mm);
+    let to_mm = |point| size.to_mm(drawing.position(point));
+
+    let mut segments: Vec<(Position, Position)> = Vec::new();
+    // Segments drawn so far, whichever way round, so ones drawn again are only plotted once
+    let mut drawn: HashSet<((i64, i64), (i64, i64))> = HashSet::new();
+    for path in drawing.machine_paths() {
+        for run in path.windows(2) {
+            let segment = (size.to_mm(run[0]), size.to_mm(run[1]));
+            let (a, b) = (key(segment.0), key(segment.1));
+            if drawn.insert((a.min(b), a.max(b))) {
+                segments.push(segment);
+            }
+        }
+    }
+    if let Some(points) = drawing.frame_points() {
+        for (idx, from) in points.iter().enumerate() {
+            let to = points[(idx + 1) % points.len()];
+            segments.push((size.to_mm(*from), size.to_mm(to)));
+        }
+    }
+    if options.include_points {
+        let ends: HashSet<(i64, i64)> = segments
+            .iter()
+            .flat_map(|(a, b)| [key(*a), key(*b)])
+            .collect();
+        for point in drawing.marked_points() {
+            let position = to_mm(point);
+            if !ends.contains(&key(position)) {
+                segments.push((position, position));
+            }
+        }
+    }
+
+    let paths: Vec<Vec<Position>> = join_segments(segments)
+        .into_iter()
+        .map(remove_collinear)
+        .collect();
+    let stats = stats(&paths, options);
+    Plot {
+        width_mm: size.width_mm,
+        height_mm: size.height_mm,
+        paths,
+        stats,
+    }
+}
+
+/// A position rounded to the tolerance, for finding positions that are the same
+fn key((x, y): Position) -> (i64, i64) {
+    (
+        (x / TOLERANCE_MM).round() as i64,
+        (y / TOLERANCE_MM).round() as i64,
+    )
+}
+
+fn same(a: Position, b: Position) -> bool {
+    distance(a, b) < TOLERANCE_MM
+}
+
+fn distance((ax, ay): Position, (bx, by): Position) -> f64 {
+    (bx - ax).hypot(by - ay)
+}
+
+/// Greedily picks the nearest segment to the pen, then follows on through any segments that
+/// continue from either of its ends
+fn join_segments(mut segments: Vec<(Position, Position)>) -> Vec<Vec<Position>> {
+    let mut paths = Vec::new();
+    let mut pen = (0_f64, 0_f64);
+
+    while !segments.is_empty() {
+        let mut nearest = (0, false, f64::MAX);
+        for (idx, (a, b)) in segments.iter().enumerate() {
+            if distance(pen, *a) < nearest.2 {
+                nearest = (idx, false, distance(pen, *a));
+            }
+            if distance(pen, *b) < nearest.2 {
+                nearest = (idx, true, distance(pen, *b));
+            }
+        }
+        let (a, b) = segments.swap_remove(nearest.0);
+        let mut path = if nearest.1 { vec![b, a] } else { vec![a, b] };
+
+        if same(a, b) {
+            path.pop();
+        } else {
+            while let Some((idx, next)) = continuation(&segments, *path.last().unwrap()) {
+                segments.swap_remove(idx);
+                path.push(next);
+            }
+            while let Some((idx, previous)) = continuation(&segments, path[0]) {
+                segments.swap_remove(idx);
+                path.insert(0, previous);
+            }
+            if distance(pen, *path.last().unwrap()) < distance(pen, path[0]) {
+                path.reverse();
+            }
+        }
+
+        pen = *path.last().unwrap();
+        paths.push(path);
+    }
+    paths
+}
+
+/// Finds a segment starting or ending at `end`, returning its index & far end
+fn continuation(segments: &[(Position, Position)], end: Position) -> Option<(usize, Position)> {
+    segments
+        .iter()
+        .enumerate()
+        .filter(|(_, (a, b))| !same(*a, *b))
+        .find_map(|(idx, (a, b))| {
+            if same(*a, end) {
+                Some((idx, *b))
+            } else if same(*b, end) {
+                Some((idx, *a))
+            } else {
+                None
+            }
+        })
+}
+
+/// Drops points in the middle of straight runs, so they're drawn as one stroke
+fn remove_collinear(path: Vec<Position>) -> Vec<Position> {
+    let mut simplified: Vec<Position> = Vec::with_capacity(path.len());
+    for position in path {
+        if let [.., a, b] = simplified[..] {
+            let (ux, uy) = (b.0 - a.0, b.1 - a.1);
+            let (vx, vy) = (position.0 - b.0, position.1 - b.1);
+            let cross = ux * vy - uy * vx;
+            let dot = ux * vx + uy * vy;
+            if cross.abs() < TOLERANCE_MM * TOLERANCE_MM && dot > 0_f64 {
+                simplified.pop();
+            }
+        }
+        simplified.push(position);
+    }
+    simplified
+}
+
+fn stats(paths: &[Vec<Position>], options: &Options) -> Stats {
+    let mut draw_mm = 0_f64;
+    let mut travel_mm = 0_f64;
+    let mut pen = (0_f64, 0_f64);
+    for path in paths {
+        travel_mm += distance(pen, path[0]);
+        draw_mm += path.windows(2).map(|w| distance(w[0], w[1])).sum::<f64>();
+        pen = *path.last().unwrap();
+    }
+    travel_mm += distance(pen, (0_f64, 0_f64));
+
+    Stats {
+        draw_mm,
+        travel_mm,
+        pen_lifts: paths.len(),
+        seconds: 60_f64 * (draw_mm / options.draw_speed + travel_mm / options.travel_speed)
+            + 2_f64 * PEN_MOVE_SECONDS * paths.len() as f64,
+    }
+}
+
+pub fn gcode(plot: &Plot, options: &Options) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "; Track Drawer plot, {:.1} x {:.1} mm",
+        plot.width_mm, plot.height_mm
+    )
+    .unwrap();
+    out.push_str("G21 ; millimetres\nG90 ; absolute positions\n");
+    writeln!(out, "{}", options.pen_up).unwrap();
+    for path in &plot.paths {
+        let (x, y) = path[0];
+        writeln!(out, "G0 X{:.3} Y{:.3} F{}", x, y, options.travel_speed).unwrap();
+        writeln!(out, "{}", options.pen_down).unwrap();
+        for (idx, (x, y)) in path.iter().enumerate().skip(1) {
+            if idx == 1 {
+                writeln!(out, "G1 X{:.3} Y{:.3} F{}", x, y, options.draw_speed).unwrap();
+            } else {
+                writeln!(out, "G1 X{:.3} Y{:.3}", x, y).unwrap();
+            }
+        }
+        writeln!(out, "{}", options.pen_up).unwrap();
+    }
+    out.push_str("G0 X0 Y0\n");
+    out
+}
+
+pub fn hpgl(plot: &Plot, options: &Options) -> String {
+    let units = |(x, y): Position| {
+        format!(
+            "{},{}",
+            (x * HPGL_UNITS_PER_MM).round(),
+            (y * HPGL_UNITS_PER_MM).round()
+        )
+    };
+    // HPGL velocity is in centimetres per second
+    let mut out = format!("IN;VS{:.1};SP1;\n", options.draw_speed / 600_f64);
+    for path in &plot.paths {
+        writeln!(out, "PU{};", units(path[0])).unwrap();
+        let rest: Vec<String> = path.iter().skip(1).map(|p| units(*p)).collect();
+        if rest.is_empty() {
+            out.push_str("PD;\n");
+        } else {
+            writeln!(out, "PD{};", rest.join(",")).unwrap();
+        }
+    }
+    out.push_str("PU0,0;SP0;\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drawing::Line;
+
+    fn options() -> Options {
+        Options {
+            width_mm: 100_f64,
+            ..Options::default()
+        }
+    }
+
+    fn drawing(lines: &[Line]) -> Drawing {
+        let mut drawing = Drawing::new();
+        drawing.set_grid_width(5).unwrap();
+        drawing.set_grid_height(3).unwrap();
+        drawing.add_lines(lines).unwrap();
+        drawing
+    }
+
+    #[test]
+    fn drops_duplicate_segments() {
+        let line = ((0, 0), (60, 0));
+        let plot = plan(&drawing(&[line, line, (line.1, line.0)]), &options());
+        let single = plan(&drawing(&[line]), &options());
+        assert_eq!(plot.paths.len(), 1);
+        assert_eq!(plot.paths, single.paths);
+        assert!((plot.stats.draw_mm - single.stats.draw_mm).abs() < TOLERANCE_MM);
+    }
+
+    #[test]
+    fn joins_continuing_segments() {
+        let (a, b, c, d) = (
+            (0_f64, 0_f64),
+            (10_f64, 0_f64),
+            (10_f64, 10_f64),
+            (0_f64, 10_f64),
+        );
+        assert_eq!(join_segments(vec![(a, b), (b, c)]), vec![vec![a, b, c]]);
+        // The second segment is drawn backwards to carry on from the first
+        assert_eq!(join_segments(vec![(a, b), (c, b)]), vec![vec![a, b, c]]);
+        assert_eq!(
+            join_segments(vec![(c, d), (a, b), (c, b)]),
+            vec![vec![a, b, c, d]]
+        );
+    }
+
+    #[test]
+    fn merges_collinear_points() {
+        let path = vec![
+            (0_f64, 0_f64),
+            (1_f64, 0_f64),
+            (2_f64, 0_f64),
+            (2_f64, 1_f64),
+            (2_f64, 3_f64),
+        ];
+        assert_eq!(
+            remove_collinear(path),
+            vec![(0_f64, 0_f64), (2_f64, 0_f64), (2_f64, 3_f64)]
+        );
+        // Doubling back isn't a straight run
+        let there_and_back = vec![(0_f64, 0_f64), (2_f64, 0_f64), (1_f64, 0_f64)];
+        assert_eq!(remove_collinear(there_and_back.clone()), there_and_back);
+    }
+
+    #[test]
+    fn plans_less_travel_than_drawing_order() {
+        // Columns drawn out of order, each line going the same way
+        let lines: Vec<_> = [4, 0, 3, 1, 2]
+            .iter()
+            .map(|column| ((column * 60, 0), (column * 60, 120)))
+            .collect();
+        let drawing = drawing(&lines);
+        let size = PhysicalSize::new(&drawing, options().width_mm);
+        let in_order: Vec<Vec<Position>> = lines
+            .iter()
+            .map(|(from, to)| {
+                vec![
+                    size.to_mm(drawing.position(*from)),
+                    size.to_mm(drawing.position(*to)),
+                ]
+            })
+            .collect();
+        let planned = plan(&drawing, &options()).stats;
+        let unplanned = stats(&in_order, &options());
+        assert!((planned.draw_mm - unplanned.draw_mm).abs() < TOLERANCE_MM);
+        assert!(planned.travel_mm < unplanned.travel_mm);
+    }
+
+    #[test]
+    fn counts_distances_and_pen_lifts() {
+        let options = options();
+        let paths = vec![vec![(3_f64, 0_f64), (3_f64, 4_f64)], vec![(6_f64, 4_f64)]];
+        let stats = stats(&paths, &options);
+        let travel = 3_f64 + 3_f64 + 52_f64.sqrt();
+        assert_eq!(stats.pen_lifts, 2);
+        assert!((stats.draw_mm - 4_f64).abs() < 1e-9);
+        assert!((stats.travel_mm - travel).abs() < 1e-9);
+        let seconds = 60_f64 * (4_f64 / options.draw_speed + travel / options.travel_speed)
+            + 4_f64 * PEN_MOVE_SECONDS;
+        assert!((stats.seconds - seconds).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,252 @@
+use tiny_skia::{
+    FillRule, GradientStop, LinearGradient, Paint, PathBuilder, Pixmap, Point, Rect, Shader,
+    SpreadMode, Stroke, Transform,
+};
+
+use crate::render::{Colour, Scene, Shape};
+
+pub const DEFAULT_WIDTH: u32 = 2000;
+/// Largest image width, keeping the pixel buffer within what a browser tab can cope with
+pub const MAX_WIDTH: u32 = 8000;
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// Width of the image in pixels, the height follows from the drawing's aspect ratio
+    pub width: u32,
+    /// Colour to fill the image with, or `None` to leave it transparent
+    pub background: Option<Colour>,
+    pub anti_alias: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            width: DEFAULT_WIDTH,
+            background: None,
+            anti_alias: true,
+        }
+    }
+}
+
+/// Rasterises a scene to PNG encoded bytes
+pub fn render(scene: &Scene, options: &Options) -> Result<Vec<u8>, String> {
+    let width = options.width.clamp(1, MAX_WIDTH);
+    let scale = width as f64 / scene.width;
+    let height = ((scene.height * scale).round() as u32).max(1);
+
+    let mut pixmap = Pixmap::new(width, height).ok_or("Image size is too large")?;
+    if let Some(Colour { r, g, b }) = options.background {
+        pixmap.fill(tiny_skia::Color::from_rgba8(r, g, b, 255));
+    }
+
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(0, 0, 0, 255);
+    paint.anti_alias = options.anti_alias;
+    let transform = Transform::from_scale(scale as f32, scale as f32);
+
+    draw_shapes(&mut pixmap, &scene.shapes, &paint, transform);
+
+    pixmap.encode_png().map_err(|err| err.to_string())
+}
+
+fn draw_shapes(pixmap: &mut Pixmap, shapes: &[Shape], paint: &Paint, transform: Transform) {
+    for shape in shapes {
+        match shape {
+            Shape::Line { from, to, width } => {
+                let mut path = PathBuilder::new();
+                path.move_to(from.0 as f32, from.1 as f32);
+                path.line_to(to.0 as f32, to.1 as f32);
+                if let Some(path) = path.finish() {
+                    let stroke = Stroke {
+                        width: *width as f32,
+                        ..Stroke::default()
+                    };
+                    pixmap.stroke_path(&path, paint, &stroke, transform, None);
+                }
+            }
+            Shape::Dot { centre, radius } => fill_dots(
+                pixmap,
+                paint,
+                transform,
+                std::slice::from_ref(centre),
+                *radius,
+            ),
+            Shape::Dots { centres, radius } => {
+                fill_dots(pixmap, paint, transform, centres, *radius)
+            }
+            Shape::Fill { top, bottom } => {
+                let (width, height) = (pixmap.width() as f32, pixmap.height() as f32);
+                let colour = |c: &Colour| tiny_skia::Color::from_rgba8(c.r, c.g, c.b, 255);
+                let mut paint = paint.clone();
+                paint.shader = LinearGradient::new(
+                    Point::from_xy(0_f32, 0_f32),
+                    Point::from_xy(0_f32, height),
+                    vec![
+                        GradientStop::new(0_f32, colour(top)),
+                        GradientStop::new(1_f32, colour(bottom)),
+                    ],
+                    SpreadMode::Pad,
+                    Transform::identity(),
+                )
+                .unwrap_or(Shader::SolidColor(colour(top)));
+                if let Some(rect) = Rect::from_xywh(0_f32, 0_f32, width, height) {
+                    pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+                }
+            }
+            Shape::Area { points } => {
+                let mut path = PathBuilder::new();
+                for (idx, (x, y)) in points.iter().enumerate() {
+                    if idx == 0 {
+                        path.move_to(*x as f32, *y as f32);
+                    } else {
+                        path.line_to(*x as f32, *y as f32);
+                    }
+                }
+                path.close();
+                if let Some(path) = path.finish() {
+                    pixmap.fill_path(&path, paint, FillRule::Winding, transform, None);
+                }
+            }
+            Shape::Polygon { points, width } | Shape::Path { points, width } => {
+                let mut path = PathBuilder::new();
+                for (idx, (x, y)) in points.iter().enumerate() {
+                    if idx == 0 {
+                        path.move_to(*x as f32, *y as f32);
+                    } else {
+                        path.line_to(*x as f32, *y as f32);
+                    }
+                }
+                if let Shape::Polygon { .. } = shape {
+                    path.close();
+                }
+                if let Some(path) = path.finish() {
+                    let stroke = Stroke {
+                        width: *width as f32,
+                        ..Stroke::default()
+                    };
+                    pixmap.stroke_path(&path, paint, &stroke, transform, None);
+                }
+            }
+            Shape::Square { centre, size } => {
+                let rect = Rect::from_xywh(
+                    (centre.0 - size) as f32,
+                    (centre.1 - size) as f32,
+                    (2_f64 * size) as f32,
+                    (2_f64 * size) as f32,
+                );
+                if let Some(rect) = rect {
+                    pixmap.fill_rect(rect, paint, transform, None);
+                }
+            }
+            Shape::Ring {
+                centre,
+                radius,
+                width,
+            } => {
+                let path =
+                    PathBuilder::from_circle(centre.0 as f32, centre.1 as f32, *radius as f32);
+                if let Some(path) = path {
+                    let stroke = Stroke {
+                        width: *width as f32,
+                        ..Stroke::default()
+                    };
+                    pixmap.stroke_path(&path, paint, &stroke, transform, None);
+                }
+            }
+            Shape::Group { colour, shapes, .. } => {
+                let mut paint = paint.clone();
+                paint.set_color_rgba8(colour.r, colour.g, colour.b, 255);
+                draw_shapes(pixmap, shapes, &paint, transform);
+            }
+        }
+    }
+}
+
+fn fill_dots(
+    pixmap: &mut Pixmap,
+    paint: &Paint,
+    transform: Transform,
+    centres: &[(f64, f64)],
+    radius: f64,
+) {
+    let mut path = PathBuilder::new();
+    for (x, y) in centres {
+        path.push_circle(*x as f32, *y as f32, radius as f32);
+    }
+    if let Some(path) = path.finish() {
+        pixmap.fill_path(&path, paint, FillRule::Winding, transform, None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Colour = Colour { r: 255, g: 0, b: 0 };
+
+    /// A horizontal line across the middle & a diagonal one, on a canvas twice as wide as tall
+    fn scene() -> Scene {
+        Scene {
+            width: 100_f64,
+            height: 50_f64,
+            shapes: vec![Shape::Group {
+                id: None,
+                colour: RED,
+                shapes: vec![
+                    Shape::Line {
+                        from: (10_f64, 25_f64),
+                        to: (90_f64, 25_f64),
+                        width: 10_f64,
+                    },
+                    Shape::Line {
+                        from: (0_f64, 0_f64),
+                        to: (100_f64, 50_f64),
+                        width: 3_f64,
+                    },
+                ],
+            }],
+        }
+    }
+
+    fn decode(options: &Options) -> Pixmap {
+        Pixmap::decode_png(&render(&scene(), options).unwrap()).unwrap()
+    }
+
+    fn rgba(pixmap: &Pixmap, x: u32, y: u32) -> (u8, u8, u8, u8) {
+        let pixel = pixmap.pixel(x, y).unwrap().demultiply();
+        (pixel.red(), pixel.green(), pixel.blue(), pixel.alpha())
+    }
+
+    #[test]
+    fn renders_lines_over_background() {
+        let pixmap = decode(&Options {
+            width: 200,
+            background: Some(Colour::WHITE),
+            anti_alias: true,
+        });
+        assert_eq!((pixmap.width(), pixmap.height()), (200, 100));
+        assert_eq!(rgba(&pixmap, 190, 10), (255, 255, 255, 255));
+        assert_eq!(rgba(&pixmap, 40, 50), (255, 0, 0, 255));
+        // The diagonal's edges blend into the background
+        assert!(pixmap
+            .pixels()
+            .iter()
+            .any(|pixel| pixel.green() != 0 && pixel.green() != 255));
+    }
+
+    #[test]
+    fn renders_hard_edges_without_anti_aliasing() {
+        let pixmap = decode(&Options {
+            width: 200,
+            background: None,
+            anti_alias: false,
+        });
+        assert_eq!((pixmap.width(), pixmap.height()), (200, 100));
+        assert_eq!(rgba(&pixmap, 190, 10), (0, 0, 0, 0));
+        assert_eq!(rgba(&pixmap, 40, 50), (255, 0, 0, 255));
+        assert!(pixmap
+            .pixels()
+            .iter()
+            .all(|pixel| pixel.alpha() == 0 || pixel.alpha() == 255));
+    }
+}
@@ -0,0 +1,107 @@
+use std::fmt::Write;
+
+use crate::render::{dots_path, points_list, Colour, Scene, Shape};
+
+/// Writes a scene as a standalone SVG file, `width` pixels wide
+pub fn render(scene: &Scene, width: u32, background: Option<Colour>) -> String {
+    let height = (width as f64 * scene.height / scene.width).round();
+    let mut out = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {} {}" width="{}" height="{}" preserveAspectRatio="xMidYMid meet">"#,
+        scene.width, scene.height, width, height
+    );
+    if let Some(colour) = background {
+        write!(
+            out,
+            r#"<rect width="100%" height="100%" fill="{}"/>"#,
+            colour.to_hex()
+        )
+        .unwrap();
+    }
+    write_shapes(&mut out, &scene.shapes, Colour::BLACK);
+    out.push_str("</svg>");
+    out
+}
+
+fn write_shapes(out: &mut String, shapes: &[Shape], colour: Colour) {
+    for shape in shapes {
+        match shape {
+            Shape::Line { from, to, width } => write!(
+                out,
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}"/>"#,
+                from.0,
+                from.1,
+                to.0,
+                to.1,
+                colour.to_hex(),
+                width
+            ),
+            Shape::Dot { centre, radius } => write!(
+                out,
+                r#"<circle cx="{}" cy="{}" r="{}"/>"#,
+                centre.0, centre.1, radius
+            ),
+            Shape::Dots { centres, radius } => {
+                write!(out, r#"<path d="{}"/>"#, dots_path(centres, *radius))
+            }
+            Shape::Fill { top, bottom } if top == bottom => write!(
+                out,
+                r#"<rect width="100%" height="100%" fill="{}"/>"#,
+                top.to_hex()
+            ),
+            Shape::Fill { top, bottom } => write!(
+                out,
+                r#"<defs><linearGradient id="background" x1="0" y1="0" x2="0" y2="1"><stop offset="0" stop-color="{}"/><stop offset="1" stop-color="{}"/></linearGradient></defs><rect width="100%" height="100%" fill="url(#background)"/>"#,
+                top.to_hex(),
+                bottom.to_hex()
+            ),
+            Shape::Polygon { points, width } => write!(
+                out,
+                r#"<polygon points="{}" fill="none" stroke="{}" stroke-width="{}"/>"#,
+                points_list(points),
+                colour.to_hex(),
+                width
+            ),
+            Shape::Area { points } => {
+                write!(out, r#"<polygon points="{}"/>"#, points_list(points))
+            }
+            Shape::Path { points, width } => write!(
+                out,
+                r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="{}"/>"#,
+                points_list(points),
+                colour.to_hex(),
+                width
+            ),
+            Shape::Square { centre, size } => write!(
+                out,
+                r#"<rect x="{}" y="{}" width="{}" height="{}"/>"#,
+                centre.0 - size,
+                centre.1 - size,
+                2_f64 * size,
+                2_f64 * size
+            ),
+            Shape::Ring {
+                centre,
+                radius,
+                width,
+            } => write!(
+                out,
+                r#"<circle cx="{}" cy="{}" r="{}" fill="none" stroke="{}" stroke-width="{}"/>"#,
+                centre.0,
+                centre.1,
+                radius,
+                colour.to_hex(),
+                width
+            ),
+            Shape::Group { id, colour, shapes } => {
+                match id {
+                    Some(id) => write!(out, r#"<g id="{}" fill="{}">"#, id, colour.to_hex()),
+                    None => write!(out, r#"<g fill="{}">"#, colour.to_hex()),
+                }
+                .unwrap();
+                write_shapes(out, shapes, *colour);
+                write!(out, "</g>")
+            }
+        }
+        .unwrap();
+    }
+}
@@ -0,0 +1,104 @@
+/// ZIP signatures for a local file header, central directory header & end of central directory
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_DIRECTORY: u32 = 0x0605_4b50;
+/// Version 2.0, the oldest that every unzip tool understands
+const VERSION: u16 = 20;
+/// General purpose flag marking file names as UTF-8
+const UTF8_NAMES: u16 = 1 << 11;
+/// 1980-01-01 00:00 in MS-DOS format, the earliest date a ZIP file can hold
+const DOS_TIME: u16 = 0;
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0_u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 == 1 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0_u32, |crc, byte| {
+        CRC_TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Packages files, given as names & contents, into a ZIP archive without compression, as
+/// the export formats are either already compressed or small
+pub fn write(files: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut directory = Vec::new();
+
+    for (name, contents) in files {
+        let offset = out.len() as u32;
+        let crc = crc32(contents);
+        let size = contents.len() as u32;
+
+        put_u32(&mut out, LOCAL_HEADER);
+        put_u16(&mut out, VERSION);
+        put_common(&mut out, crc, size, name);
+        put_u16(&mut out, 0); // extra field length
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(contents);
+
+        put_u32(&mut directory, CENTRAL_HEADER);
+        put_u16(&mut directory, VERSION); // made by
+        put_u16(&mut directory, VERSION); // needed to extract
+        put_common(&mut directory, crc, size, name);
+        put_u16(&mut directory, 0); // extra field length
+        put_u16(&mut directory, 0); // comment length
+        put_u16(&mut directory, 0); // disk number
+        put_u16(&mut directory, 0); // internal attributes
+        put_u32(&mut directory, 0); // external attributes
+        put_u32(&mut directory, offset);
+        directory.extend_from_slice(name.as_bytes());
+    }
+
+    let directory_offset = out.len() as u32;
+    let directory_size = directory.len() as u32;
+    out.append(&mut directory);
+    put_u32(&mut out, END_OF_DIRECTORY);
+    put_u16(&mut out, 0); // this disk
+    put_u16(&mut out, 0); // disk with the directory
+    put_u16(&mut out, files.len() as u16);
+    put_u16(&mut out, files.len() as u16);
+    put_u32(&mut out, directory_size);
+    put_u32(&mut out, directory_offset);
+    put_u16(&mut out, 0); // comment length
+    out
+}
+
+/// Fields shared by the local & central headers, from the flags up to the name length
+fn put_common(out: &mut Vec<u8>, crc: u32, size: u32, name: &str) {
+    put_u16(out, UTF8_NAMES);
+    put_u16(out, 0); // stored, without compression
+    put_u16(out, DOS_TIME);
+    put_u16(out, DOS_DATE);
+    put_u32(out, crc);
+    put_u32(out, size);
+    put_u32(out, size);
+    put_u16(out, name.len() as u16);
+}
+
+fn put_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
@@ -0,0 +1,728 @@
+use seed::{prelude::*, *};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::EXPORT_KEY;
+use crate::utils;
+use track_drawer::drawing::Drawing;
+use track_drawer::export::{
+    self, dxf, outline, pdf, plotter, png, svg, zip, FilenameFields, Format, PhysicalSize,
+};
+use track_drawer::render::Colour;
+use track_drawer::renderer::Renderer;
+
+/// Export choices, remembered between visits
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Settings {
+    pub format: Format,
+    /// Filename without its extension, see `export::filename` for the fields it can use
+    pub filename: String,
+    pub include_points: bool,
+    /// Image width for SVG & PNG
+    pub width_px: u32,
+    /// Physical width for plotter & DXF output
+    pub width_mm: f64,
+    pub background: bool,
+    /// Background colour picked for images, kept while the background is transparent
+    pub background_colour: Colour,
+    pub anti_alias: bool,
+    pub paper: pdf::PaperSize,
+    pub landscape: bool,
+    pub margin_mm: f64,
+    pub draw_speed: f64,
+    pub travel_speed: f64,
+    pub pen_up: String,
+    pub pen_down: String,
+    /// Outline the strokes in DXF output
+    pub outline: bool,
+    /// Export each visible layer to its own file, such as for plotting each with its own pen
+    pub per_layer: bool,
+    /// Style to export in, `None` keeping each drawing's own
+    pub renderer: Option<Renderer>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        let pdf = pdf::Options::default();
+        let plotter = plotter::Options::default();
+        Settings {
+            format: Format::default(),
+            filename: "{name}-{date}".to_string(),
+            include_points: false,
+            width_px: png::DEFAULT_WIDTH,
+            width_mm: plotter.width_mm,
+            background: false,
+            background_colour: Colour::WHITE,
+            anti_alias: true,
+            paper: pdf.paper,
+            landscape: pdf.landscape,
+            margin_mm: pdf.margin_mm,
+            draw_speed: plotter.draw_speed,
+            travel_speed: plotter.travel_speed,
+            pen_up: plotter.pen_up,
+            pen_down: plotter.pen_down,
+            outline: false,
+            per_layer: false,
+            renderer: None,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Settings {
+        LocalStorage::get(EXPORT_KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        LocalStorage::insert(EXPORT_KEY, self).expect("Saving export settings failed")
+    }
+
+    fn background(&self) -> Option<Colour> {
+        Some(self.background_colour).filter(|_| self.background)
+    }
+
+    fn plotter_options(&self) -> plotter::Options {
+        plotter::Options {
+            width_mm: self.width_mm,
+            draw_speed: self.draw_speed,
+            travel_speed: self.travel_speed,
+            pen_up: self.pen_up.clone(),
+            pen_down: self.pen_down.clone(),
+            include_points: self.include_points,
+        }
+    }
+
+    /// The size a drawing is exported at in the chosen format, in pixels or millimetres
+    fn size(&self, drawing: &Drawing) -> String {
+        let aspect_ratio = drawing.view_height() / drawing.view_width();
+        match self.format {
+            Format::Svg | Format::Png => {
+                let width = if self.format == Format::Png {
+                    self.width_px.clamp(1, png::MAX_WIDTH)
+                } else {
+                    self.width_px
+                };
+                let height = (width as f64 * aspect_ratio).round().max(1_f64);
+                format!("{}x{}px", width, height)
+            }
+            Format::OutlineSvg => {
+                format!("{:.0}x{:.0}px", drawing.view_width(), drawing.view_height())
+            }
+            Format::Pdf => {
+                let (width, height) = self.paper.size_mm();
+                let (width, height) = if self.landscape {
+                    (height, width)
+                } else {
+                    (width, height)
+                };
+                format!("{:.0}x{:.0}mm", width, height)
+            }
+            Format::Gcode | Format::Hpgl | Format::Dxf => {
+                let size = PhysicalSize::new(drawing, self.width_mm);
+                format!("{:.0}x{:.0}mm", size.width_mm, size.height_mm)
+            }
+        }
+    }
+
+    /// Converts a drawing to the chosen format
+    fn export(&self, drawing: &Drawing, caption: &str) -> Result<Vec<u8>, String> {
+        let scene = drawing.scene_with_points(self.include_points);
+        Ok(match self.format {
+            Format::Svg => svg::render(&scene, self.width_px, self.background()).into_bytes(),
+            Format::Png => png::render(
+                &scene,
+                &png::Options {
+                    width: self.width_px,
+                    background: self.background(),
+                    anti_alias: self.anti_alias,
+                },
+            )?,
+            Format::Pdf => {
+                let options = pdf::Options {
+                    paper: self.paper,
+                    landscape: self.landscape,
+                    margin_mm: self.margin_mm,
+                    ..pdf::Options::default()
+                };
+                let caption = Some(caption.to_string()).filter(|caption| !caption.is_empty());
+                pdf::render(&[(scene, caption)], &options)
+            }
+            Format::Gcode | Format::Hpgl => {
+                let options = self.plotter_options();
+                let plot = plotter::plan(drawing, &options);
+                if self.format == Format::Gcode {
+                    plotter::gcode(&plot, &options).into_bytes()
+                } else {
+                    plotter::hpgl(&plot, &options).into_bytes()
+                }
+            }
+            Format::Dxf => {
+                let options = dxf::Options {
+                    width_mm: self.width_mm,
+                    include_points: self.include_points,
+                    outline: self.outline,
+                };
+                dxf::render(drawing, &options).into_bytes()
+            }
+            Format::OutlineSvg => {
+                outline::svg(&drawing.machine_scene(self.include_points)).into_bytes()
+            }
+        })
+    }
+}
+
+pub struct Model {
+    open: bool,
+    settings: Settings,
+    /// Caption printed under PDF exports, each drawing's name, seed & date unless it's changed
+    caption: Option<String>,
+    error: Option<String>,
+    /// Where the export is saved, worked out as the settings change rather than on every view
+    saves_as: String,
+    /// The caption a single drawing gets unless it's changed
+    default_caption: String,
+    /// Estimates for plotting a single drawing
+    plot_stats: Option<plotter::Stats>,
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Model {
+            open: false,
+            settings: Settings::load(),
+            caption: None,
+            error: None,
+            saves_as: String::new(),
+            default_caption: String::new(),
+            plot_stats: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Msg {
+    Open,
+    Close,
+    Export,
+    ChangeFormat(Format),
+    ChangeFilename(String),
+    TogglePoints,
+    ChangeWidthPx(u32),
+    ChangeWidthMm(f64),
+    ToggleBackground,
+    ChangeBackground(Colour),
+    ToggleAntiAlias,
+    ChangePaper(pdf::PaperSize),
+    ToggleLandscape,
+    ChangeMargin(f64),
+    ChangeCaption(String),
+    ChangeDrawSpeed(f64),
+    ChangeTravelSpeed(f64),
+    ChangePenUp(String),
+    ChangePenDown(String),
+    ToggleOutline,
+    TogglePerLayer,
+    ChangeRenderer(Option<Renderer>),
+}
+
+/// Values for the filename template, naming drawings without a title "Track"
+fn filename_fields<'a>(
+    settings: &Settings,
+    drawing: &'a Drawing,
+    date: &'a str,
+) -> FilenameFields<'a> {
+    FilenameFields {
+        name: if drawing.title.is_empty() {
+            "Track"
+        } else {
+            &drawing.title
+        },
+        seed: drawing.seed,
+        date,
+        size: settings.size(drawing),
+    }
+}
+
+/// The drawings to export in the chosen style, split into a drawing for each visible layer if
+/// exporting by layer
+fn export_drawings(settings: &Settings, drawings: &[&Drawing]) -> Vec<Drawing> {
+    let mut drawings: Vec<Drawing> = drawings.iter().map(|drawing| (*drawing).clone()).collect();
+    if let Some(renderer) = settings.renderer {
+        drawings
+            .iter_mut()
+            .for_each(|drawing| drawing.renderer = renderer);
+    }
+    if !settings.per_layer {
+        return drawings;
+    }
+    let mut split = Vec::new();
+    for drawing in drawings {
+        for (idx, layer) in drawing.layers().iter().enumerate() {
+            if !layer.visible {
+                continue;
+            }
+            if let Some(mut layer_drawing) = drawing.layer_drawing(idx) {
+                layer_drawing.title = format!("{} {}", drawing.title, layer.name);
+                split.push(layer_drawing);
+            }
+        }
+    }
+    split
+}
+
+/// Describes the contents of a bulk export, saved alongside the drawings as `manifest.json`
+#[derive(Serialize)]
+struct Manifest<'a> {
+    format: &'static str,
+    exported: &'a str,
+    drawings: Vec<ManifestEntry<'a>>,
+}
+
+#[derive(Serialize)]
+struct ManifestEntry<'a> {
+    file: &'a str,
+    title: &'a str,
+    description: &'a str,
+    tags: &'a [String],
+    created: &'a Option<String>,
+    modified: &'a Option<String>,
+    grid_width: u16,
+    grid_height: u16,
+    lines: usize,
+}
+
+/// Exports each drawing, naming the files from the template & numbering any that clash
+fn export_files(
+    settings: &Settings,
+    drawings: &[&Drawing],
+    caption: Option<&str>,
+    date: &str,
+) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let mut files: Vec<(String, Vec<u8>)> = Vec::new();
+    for drawing in drawings {
+        let filename = export::filename(
+            &settings.filename,
+            &filename_fields(settings, drawing, date),
+            drawing,
+            settings.format,
+        );
+        let mut unique = filename.clone();
+        let mut count = 1;
+        while files.iter().any(|(name, _)| *name == unique) {
+            count += 1;
+            let (stem, extension) = filename.rsplit_once('.').unwrap_or((&filename, ""));
+            unique = format!("{}-{}.{}", stem, count, extension);
+        }
+        let caption = caption
+            .map(str::to_string)
+            .unwrap_or_else(|| pdf::caption(drawing, date));
+        files.push((unique, settings.export(drawing, &caption)?));
+    }
+    Ok(files)
+}
+
+/// Packages exported files into a ZIP archive with a manifest describing them
+fn bundle(
+    settings: &Settings,
+    drawings: &[&Drawing],
+    mut files: Vec<(String, Vec<u8>)>,
+    date: &str,
+) -> Vec<u8> {
+    let manifest = Manifest {
+        format: settings.format.name(),
+        exported: date,
+        drawings: files
+            .iter()
+            .zip(drawings)
+            .map(|((file, _), drawing)| ManifestEntry {
+                file,
+                title: &drawing.title,
+                description: &drawing.description,
+                tags: drawing.tags(),
+                created: &drawing.created,
+                modified: &drawing.modified,
+                grid_width: drawing.grid_width,
+                grid_height: drawing.grid_height,
+                lines: drawing.line_count(),
+            })
+            .collect(),
+    };
+    let manifest = serde_json::to_vec_pretty(&manifest).unwrap();
+    files.push(("manifest.json".to_string(), manifest));
+    zip::write(&files)
+}
+
+fn zip_filename(date: &str) -> String {
+    format!("Tracks-{}.zip", date)
+}
+
+/// Updates where the export is saved & the plotting estimates for the current settings
+fn preview(model: &mut Model, drawings: &[&Drawing]) {
+    let settings = &model.settings;
+    let date = utils::today();
+    let files = export_drawings(settings, drawings);
+    model.saves_as = match &files[..] {
+        [drawing] => format!(
+            "Saves as {}",
+            export::filename(
+                &settings.filename,
+                &filename_fields(settings, drawing, &date),
+                drawing,
+                settings.format
+            )
+        ),
+        _ => format!("Saves {} files in {}", files.len(), zip_filename(&date)),
+    };
+    model.default_caption = match &files[..] {
+        [drawing] => pdf::caption(drawing, &date),
+        _ => String::new(),
+    };
+    model.plot_stats = match (settings.format, &files[..]) {
+        (Format::Gcode | Format::Hpgl, [drawing]) => {
+            Some(plotter::plan(drawing, &settings.plotter_options()).stats)
+        }
+        _ => None,
+    };
+}
+
+/// Handles a message for the dialog, exporting `drawings` as a single file, or a ZIP file
+/// when there are several
+pub fn update(msg: Msg, model: &mut Model, drawings: &[&Drawing]) {
+    let settings = &mut model.settings;
+    match msg {
+        Msg::Open => {
+            model.open = true;
+            model.error = None;
+            model.caption = None;
+        }
+        Msg::Close => {
+            model.open = false;
+        }
+        Msg::Export => {
+            let date = utils::today();
+            let drawings = export_drawings(settings, drawings);
+            let drawings: Vec<&Drawing> = drawings.iter().collect();
+            let drawings = &drawings[..];
+            match export_files(settings, drawings, model.caption.as_deref(), &date) {
+                Ok(mut files) if files.len() == 1 => {
+                    let (filename, bytes) = files.remove(0);
+                    utils::download_bytes(&bytes, settings.format.mime_type(), &filename);
+                    model.open = false;
+                }
+                Ok(files) => {
+                    let bytes = bundle(settings, drawings, files, &date);
+                    utils::download_bytes(&bytes, "application/zip", &zip_filename(&date));
+                    model.open = false;
+                }
+                Err(err) => model.error = Some(err),
+            }
+        }
+        Msg::ChangeFormat(format) => settings.format = format,
+        Msg::ChangeFilename(filename) => settings.filename = filename,
+        Msg::TogglePoints => settings.include_points = !settings.include_points,
+        Msg::ChangeWidthPx(width) => settings.width_px = width.clamp(1, png::MAX_WIDTH),
+        Msg::ChangeWidthMm(width) => settings.width_mm = width.max(1_f64),
+        Msg::ToggleBackground => settings.background = !settings.background,
+        Msg::ChangeBackground(colour) => {
+            settings.background_colour = colour;
+            settings.background = true;
+        }
+        Msg::ToggleAntiAlias => settings.anti_alias = !settings.anti_alias,
+        Msg::ChangePaper(paper) => settings.paper = paper,
+        Msg::ToggleLandscape => settings.landscape = !settings.landscape,
+        Msg::ChangeMargin(margin) => settings.margin_mm = margin.max(0_f64),
+        Msg::ChangeCaption(caption) => model.caption = Some(caption),
+        Msg::ChangeDrawSpeed(speed) => settings.draw_speed = speed.max(1_f64),
+        Msg::ChangeTravelSpeed(speed) => settings.travel_speed = speed.max(1_f64),
+        Msg::ChangePenUp(command) => settings.pen_up = command,
+        Msg::ChangePenDown(command) => settings.pen_down = command,
+        Msg::ToggleOutline => settings.outline = !settings.outline,
+        Msg::TogglePerLayer => settings.per_layer = !settings.per_layer,
+        Msg::ChangeRenderer(renderer) => settings.renderer = renderer,
+    }
+    model.settings.save();
+    if model.open {
+        preview(model, drawings);
+    }
+}
+
+pub fn view(model: &Model, drawings: &[&Drawing]) -> Option<Node<Msg>> {
+    if !model.open {
+        return None;
+    }
+    let settings = &model.settings;
+    Some(div![
+        C!["fixed inset-0 bg-gray-900 bg-opacity-50 flex justify-center items-start pt-16 z-10"],
+        ev(Ev::Click, |e| {
+            e.stop_propagation();
+            Msg::Close
+        }),
+        div![
+            C!["w-1/3 bg-white rounded-md shadow-lg p-4 flex flex-col max-h-screen overflow-auto"],
+            ev(Ev::Click, |e| e.stop_propagation()),
+            h2![
+                C!["text-lg font-semibold mb-2"],
+                if drawings.len() == 1 {
+                    "Export".to_string()
+                } else {
+                    format!("Export {} drawings", drawings.len())
+                }
+            ],
+            label![C!["mb-1"], "Format:"],
+            select![
+                C!["form-select w-full"],
+                Format::ALL.iter().enumerate().map(|(idx, format)| option![
+                    attrs! {
+                        At::Value => idx,
+                        At::Selected => (*format == settings.format).as_at_value()
+                    },
+                    format.name()
+                ]),
+                input_ev(Ev::Change, |value| {
+                    value
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|idx| Format::ALL.get(idx))
+                        .map(|format| Msg::ChangeFormat(*format))
+                })
+            ],
+            label![C!["mt-2 mb-1"], "Filename:"],
+            input![
+                C!["form-input w-full"],
+                attrs! {At::Type => "text", At::Value => settings.filename},
+                input_ev(Ev::Input, Msg::ChangeFilename)
+            ],
+            div![
+                C!["text-sm text-gray-500 mt-1"],
+                "Use {name}, {seed}, {date}, {grid} & {size}. ",
+                &model.saves_as
+            ],
+            label![C!["mt-2 mb-1"], "Style:"],
+            select![
+                C!["form-select w-full"],
+                option![
+                    attrs! {At::Value => "", At::Selected => settings.renderer.is_none().as_at_value()},
+                    "As drawn"
+                ],
+                Renderer::ALL.iter().enumerate().map(|(idx, renderer)| option![
+                    attrs! {
+                        At::Value => idx,
+                        At::Selected => (Some(*renderer) == settings.renderer).as_at_value()
+                    },
+                    renderer.name()
+                ]),
+                input_ev(Ev::Change, |value| {
+                    Msg::ChangeRenderer(
+                        value
+                            .parse::<usize>()
+                            .ok()
+                            .and_then(|idx| Renderer::ALL.get(idx))
+                            .copied(),
+                    )
+                })
+            ],
+            checkbox("Grid points", settings.include_points, || Msg::TogglePoints),
+            checkbox("One file per layer", settings.per_layer, || {
+                Msg::TogglePerLayer
+            }),
+            format_view(model),
+            model
+                .error
+                .as_ref()
+                .map(|err| div![C!["text-sm text-red-600 mt-2"], err]),
+            div![
+                C!["flex justify-end space-x-2 mt-4"],
+                button![
+                    C!["py-2 px-4 rounded-md hover:bg-gray-200 focus:outline-none"],
+                    "Cancel",
+                    ev(Ev::Click, |_| Msg::Close)
+                ],
+                button![
+                    C![
+                        "py-2 px-4 bg-blue-500 text-white font-semibold rounded-md shadow-md hover:bg-blue-700",
+                        "focus:outline-none focus:ring-4 focus:ring-indigo-400 focus:ring-opacity-75"
+                    ],
+                    format!("Download {}", settings.format.name()),
+                    ev(Ev::Click, |_| Msg::Export)
+                ]
+            ]
+        ]
+    ])
+}
+
+/// Options that only apply to the chosen format
+fn format_view(model: &Model) -> Vec<Node<Msg>> {
+    let settings = &model.settings;
+    match settings.format {
+        Format::Svg | Format::Png => vec![
+            label![C!["mt-2 mb-1"], "Width (px):"],
+            input![
+                C!["form-input w-full"],
+                attrs! {
+                    At::Type => "number",
+                    At::Min => 1,
+                    At::Max => png::MAX_WIDTH,
+                    At::Step => 100,
+                    At::Value => settings.width_px
+                },
+                input_ev(Ev::Change, |value| value
+                    .parse()
+                    .ok()
+                    .map(Msg::ChangeWidthPx))
+            ],
+            label![
+                C!["flex items-center w-full mt-2"],
+                "Background",
+                input![
+                    C!["form-checkbox ml-2"],
+                    attrs! {
+                        At::Type => "checkbox",
+                        At::Checked => settings.background.as_at_value()
+                    },
+                    ev(Ev::Click, |_| Msg::ToggleBackground)
+                ],
+                input![
+                    C!["ml-2"],
+                    attrs! {
+                        At::Type => "color",
+                        At::Value => settings.background_colour.to_hex()
+                    },
+                    input_ev(Ev::Input, |value| Colour::from_hex(&value)
+                        .map(Msg::ChangeBackground))
+                ]
+            ],
+            if settings.format == Format::Png {
+                checkbox("Anti-aliasing", settings.anti_alias, || {
+                    Msg::ToggleAntiAlias
+                })
+            } else {
+                empty![]
+            },
+        ],
+        Format::Pdf => vec![
+            label![C!["mt-2 mb-1"], "Paper:"],
+            select![
+                C!["form-select w-full"],
+                pdf::PaperSize::ALL
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, paper)| option![
+                        attrs! {
+                            At::Value => idx,
+                            At::Selected => (*paper == settings.paper).as_at_value()
+                        },
+                        paper.name()
+                    ]),
+                input_ev(Ev::Change, |value| {
+                    value
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|idx| pdf::PaperSize::ALL.get(idx))
+                        .map(|paper| Msg::ChangePaper(*paper))
+                })
+            ],
+            checkbox("Landscape", settings.landscape, || Msg::ToggleLandscape),
+            label![C!["mt-2 mb-1"], "Margin (mm):"],
+            number_input(settings.margin_mm, 0_f64, Msg::ChangeMargin),
+            label![C!["mt-2 mb-1"], "Caption:"],
+            input![
+                C!["form-input w-full"],
+                attrs! {
+                    At::Type => "text",
+                    At::Value => model.caption.as_ref().unwrap_or(&model.default_caption),
+                    At::Placeholder => "Each drawing's name, seed & date"
+                },
+                input_ev(Ev::Input, Msg::ChangeCaption)
+            ],
+        ],
+        Format::Gcode | Format::Hpgl => {
+            vec![
+                label![C!["mt-2 mb-1"], "Width (mm):"],
+                number_input(settings.width_mm, 1_f64, Msg::ChangeWidthMm),
+                label![C!["mt-2 mb-1"], "Draw speed (mm/min):"],
+                number_input(settings.draw_speed, 1_f64, Msg::ChangeDrawSpeed),
+                label![C!["mt-2 mb-1"], "Travel speed (mm/min):"],
+                number_input(settings.travel_speed, 1_f64, Msg::ChangeTravelSpeed),
+                if settings.format == Format::Gcode {
+                    div![
+                        label![C!["mt-2 mb-1"], "Pen up G-code:"],
+                        input![
+                            C!["form-input w-full"],
+                            attrs! {At::Type => "text", At::Value => settings.pen_up},
+                            input_ev(Ev::Change, Msg::ChangePenUp)
+                        ],
+                        label![C!["mt-2 mb-1"], "Pen down G-code:"],
+                        input![
+                            C!["form-input w-full"],
+                            attrs! {At::Type => "text", At::Value => settings.pen_down},
+                            input_ev(Ev::Change, Msg::ChangePenDown)
+                        ],
+                    ]
+                } else {
+                    empty![]
+                },
+                model.plot_stats.map(stats_view).unwrap_or(empty![]),
+            ]
+        }
+        Format::Dxf => vec![
+            label![C!["mt-2 mb-1"], "Width (mm):"],
+            number_input(settings.width_mm, 1_f64, Msg::ChangeWidthMm),
+            checkbox("Outline strokes", settings.outline, || Msg::ToggleOutline),
+        ],
+        Format::OutlineSvg => vec![],
+    }
+}
+
+/// Estimates for plotting a single drawing
+fn stats_view(stats: plotter::Stats) -> Node<Msg> {
+    dl![
+        C!["grid grid-cols-2 gap-1 text-sm mt-2 w-full text-gray-600"],
+        dt!["Drawing"],
+        dd![format!("{:.2} m", stats.draw_mm / 1000_f64)],
+        dt!["Pen up travel"],
+        dd![format!("{:.2} m", stats.travel_mm / 1000_f64)],
+        dt!["Pen lifts"],
+        dd![stats.pen_lifts.to_string()],
+        dt!["Plot time"],
+        dd![format!(
+            "{}m {:02}s",
+            (stats.seconds / 60_f64) as u64,
+            stats.seconds as u64 % 60
+        )],
+    ]
+}
+
+fn checkbox(
+    text: &str,
+    checked: bool,
+    on_click: impl FnOnce() -> Msg + Clone + 'static,
+) -> Node<Msg> {
+    label![
+        C!["flex items-center w-full mt-2"],
+        text,
+        input![
+            C!["form-checkbox ml-2"],
+            attrs! {
+                At::Type => "checkbox",
+                At::Checked => checked.as_at_value()
+            },
+            ev(Ev::Click, |_| on_click())
+        ]
+    ]
+}
+
+fn number_input(
+    value: f64,
+    min: f64,
+    on_change: impl FnOnce(f64) -> Msg + Clone + 'static,
+) -> Node<Msg> {
+    input![
+        C!["form-input w-full"],
+        attrs! {
+            At::Type => "number",
+            At::Min => min,
+            At::Value => value
+        },
+        input_ev(Ev::Change, |value| value.parse().ok().map(on_change))
+    ]
+}
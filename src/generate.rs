@@ -0,0 +1,137 @@
+pub mod breed;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::drawing::{Drawing, DrawingError, Line, Point, Subgrid, CELL_STEPS, MAX_GRID_SIZE};
+
+/// Choices for generating whole drawings
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(default)]
+pub struct Settings {
+    pub grid_width: u16,
+    pub rows: u16,
+    /// Random lines added before moving on to the next row
+    pub lines_per_row: u16,
+    pub includes_points: bool,
+    /// Points between grid points that lines can also be drawn to
+    pub subgrid: Subgrid,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            grid_width: 4,
+            rows: 6,
+            lines_per_row: 2,
+            includes_points: true,
+            subgrid: Subgrid::Whole,
+        }
+    }
+}
+
+/// How much of a drawing a remix keeps before generating the rest
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Keep {
+    /// The first lines drawn
+    Lines(usize),
+    /// Every line within the first rows
+    Rows(u16),
+}
+
+impl Default for Keep {
+    fn default() -> Self {
+        Keep::Lines(4)
+    }
+}
+
+/// The sub-grid & custom points from the top of the first of `rows` down to the last, the
+/// upper bound exclusive
+fn points_in_rows(drawing: &Drawing, rows: (i16, i16)) -> Vec<Point> {
+    let (top, bottom) = (rows.0 * CELL_STEPS, (rows.1 - 1) * CELL_STEPS);
+    drawing
+        .snap_points()
+        .filter(|(_, y)| *y >= top && *y <= bottom)
+        .collect()
+}
+
+/// A random point lines can be drawn to within `rows`, the upper bound exclusive
+pub fn random_point(rng: &mut impl Rng, drawing: &Drawing, rows: (i16, i16)) -> Point {
+    let points = points_in_rows(drawing, rows);
+    points.choose(rng).copied().unwrap_or((0, 0))
+}
+
+/// A random line with both ends within `rows`, the upper bound exclusive
+pub fn random_line(rng: &mut impl Rng, drawing: &Drawing, rows: (i16, i16)) -> Line {
+    let points = points_in_rows(drawing, rows);
+    let mut end = || points.choose(rng).copied().unwrap_or((0, 0));
+    (end(), end())
+}
+
+/// Generates a drawing the way one is made on the draw page, adding random lines between
+/// the last two rows then another row, until the grid has enough rows
+pub fn generate(settings: &Settings, seed: u64) -> Drawing {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut drawing = Drawing::new();
+    drawing.grid_width = settings.grid_width.clamp(1, MAX_GRID_SIZE);
+    let rows = settings.rows.clamp(1, MAX_GRID_SIZE);
+    drawing.grid_height = rows.min(2);
+    drawing.includes_points = settings.includes_points;
+    drawing.subgrid = settings.subgrid;
+
+    let mut lines = Vec::new();
+    loop {
+        let height = drawing.grid_height as i16;
+        for _ in 0..settings.lines_per_row {
+            lines.push(random_line(
+                &mut rng,
+                &drawing,
+                ((height - 2).max(0), height),
+            ));
+        }
+        if drawing.grid_height >= rows {
+            break;
+        }
+        drawing.add_row();
+    }
+    drawing
+        .replace_lines(lines)
+        .expect("A new drawing has no locked layers");
+    drawing.seed = Some(seed);
+    drawing
+}
+
+/// Keeps the start of a drawing & replaces the rest of its lines with random ones from
+/// `seed`, each spanning the same rows as the line it replaces. Only the active layer is
+/// remixed, so it mustn't be locked
+pub fn remix(drawing: &Drawing, keep: Keep, seed: u64) -> Result<Drawing, DrawingError> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let lines = drawing
+        .lines()
+        .iter()
+        .enumerate()
+        .map(|(idx, line @ ((_, from_y), (_, to_y)))| {
+            let (top, bottom) = (*from_y.min(to_y), *from_y.max(to_y));
+            let kept = match keep {
+                Keep::Lines(count) => idx < count,
+                Keep::Rows(rows) => bottom <= (rows as i16 - 1) * CELL_STEPS,
+            };
+            if kept {
+                *line
+            } else {
+                // The rows either side of any ends between rows
+                let rows = (top / CELL_STEPS, (bottom + CELL_STEPS - 1) / CELL_STEPS + 1);
+                random_line(&mut rng, drawing, rows)
+            }
+        })
+        .collect();
+
+    let mut remixed = drawing.clone();
+    remixed.replace_lines(lines)?;
+    remixed.seed = Some(seed);
+    remixed.created = None;
+    remixed.modified = None;
+    Ok(remixed)
+}
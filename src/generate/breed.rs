@@ -0,0 +1,225 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use super::random_line;
+use crate::drawing::{Canvas, Drawing, DrawingError, Line, Point, CELL_STEPS};
+
+/// Chances, each from 0 to 1, of the changes `mutate` makes
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Mutation {
+    /// Moving each end of a line to a neighbouring sub-grid point
+    pub jitter: f64,
+    /// Dropping each line
+    pub remove: f64,
+    /// Adding a random line
+    pub add: f64,
+    /// Showing or hiding the points, & separately changing the canvas
+    pub style: f64,
+}
+
+impl Default for Mutation {
+    fn default() -> Self {
+        Mutation {
+            jitter: 0.1,
+            remove: 0.05,
+            add: 0.3,
+            style: 0.1,
+        }
+    }
+}
+
+fn chance(rng: &mut impl Rng, probability: f64) -> bool {
+    rng.gen_bool(probability.clamp(0_f64, 1_f64))
+}
+
+/// The upper of the rows a line spans, or the row above if it starts between rows
+fn top_row(((_, from_y), (_, to_y)): &Line) -> i16 {
+    from_y.min(to_y) / CELL_STEPS
+}
+
+/// A child on the first parent's grid, taking the lines starting in each row from either
+/// parent, & its style from either. The lines go in the first parent's active layer, so it
+/// mustn't be locked
+pub fn crossover(
+    first: &Drawing,
+    second: &Drawing,
+    rng: &mut impl Rng,
+) -> Result<Drawing, DrawingError> {
+    let mut lines = Vec::new();
+    for row in 0..first.grid_height as i16 {
+        let parent = if rng.gen_bool(0.5) { first } else { second };
+        lines.extend(
+            parent
+                .lines()
+                .iter()
+                .filter(|line| top_row(line) == row)
+                .filter(|(from, to)| first.contains(*from) && first.contains(*to)),
+        );
+    }
+
+    let mut child = first.clone();
+    child.replace_lines(lines)?;
+    if rng.gen_bool(0.5) {
+        child.includes_points = second.includes_points;
+        child.set_canvas(second.canvas());
+        child.set_margin(second.margin());
+    }
+    Ok(child)
+}
+
+/// Moves a point by up to one sub-grid step each way, if that stays within the grid
+fn jitter(drawing: &Drawing, (x, y): Point, rng: &mut impl Rng) -> Point {
+    let step = drawing.subgrid.step();
+    let moved = (
+        x + step * rng.gen_range(-1..=1),
+        y + step * rng.gen_range(-1..=1),
+    );
+    if drawing.contains(moved) {
+        moved
+    } else {
+        (x, y)
+    }
+}
+
+/// Randomly jitters line ends, drops & adds lines, & changes the style of a drawing. Only the
+/// active layer's lines change, so it mustn't be locked
+pub fn mutate(
+    drawing: &Drawing,
+    mutation: &Mutation,
+    rng: &mut impl Rng,
+) -> Result<Drawing, DrawingError> {
+    let mut lines = Vec::new();
+    for (from, to) in drawing.lines() {
+        if chance(rng, mutation.remove) {
+            continue;
+        }
+        let from = if chance(rng, mutation.jitter) {
+            jitter(drawing, *from, rng)
+        } else {
+            *from
+        };
+        let to = if chance(rng, mutation.jitter) {
+            jitter(drawing, *to, rng)
+        } else {
+            *to
+        };
+        lines.push((from, to));
+    }
+    if chance(rng, mutation.add) {
+        let height = drawing.grid_height as i16;
+        let top = rng.gen_range(0..height);
+        lines.push(random_line(rng, drawing, (top, (top + 2).min(height))));
+    }
+
+    let mut mutated = drawing.clone();
+    mutated.replace_lines(lines)?;
+    if chance(rng, mutation.style) {
+        mutated.toggle_include_points();
+    }
+    if chance(rng, mutation.style) {
+        if let Some(canvas) = Canvas::ALL.choose(rng) {
+            mutated.set_canvas(*canvas);
+        }
+    }
+    Ok(mutated)
+}
+
+/// Crosses two parents picked from `parents` & mutates the child, or `None` without parents or
+/// when the first parent picked has its active layer locked
+pub fn breed(parents: &[Drawing], mutation: &Mutation, seed: u64) -> Option<Drawing> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut picked = parents.choose_multiple(&mut rng, 2);
+    let first = picked.next()?;
+    let second = picked.next().unwrap_or(first);
+
+    let child = crossover(first, second, &mut rng).ok()?;
+    let mut child = mutate(&child, mutation, &mut rng).ok()?;
+    child.seed = Some(seed);
+    child.created = None;
+    child.modified = None;
+    Some(child)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drawing::Subgrid;
+    use crate::generate::{generate, Settings};
+
+    fn parents() -> [Drawing; 2] {
+        let settings = Settings {
+            subgrid: Subgrid::Thirds,
+            ..Settings::default()
+        };
+        [
+            generate(&settings, 1),
+            generate(
+                &Settings {
+                    grid_width: 6,
+                    ..settings
+                },
+                2,
+            ),
+        ]
+    }
+
+    fn row_lines(drawing: &Drawing, row: i16) -> Vec<Line> {
+        drawing
+            .lines()
+            .iter()
+            .filter(|line| top_row(line) == row)
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_breeds_same_child() {
+        let parents = parents();
+        let first = breed(&parents, &Mutation::default(), 7).unwrap();
+        let second = breed(&parents, &Mutation::default(), 7).unwrap();
+        assert_eq!(first.lines(), second.lines());
+        assert_eq!(first.content_hash(), second.content_hash());
+    }
+
+    #[test]
+    fn crossover_takes_each_row_from_a_parent() {
+        let [first, second] = parents();
+        for seed in 0..20 {
+            let child = crossover(&first, &second, &mut StdRng::seed_from_u64(seed)).unwrap();
+            for row in 0..first.grid_height as i16 {
+                let lines = row_lines(&child, row);
+                let from_second: Vec<Line> = row_lines(&second, row)
+                    .into_iter()
+                    .filter(|(from, to)| first.contains(*from) && first.contains(*to))
+                    .collect();
+                assert!(lines == row_lines(&first, row) || lines == from_second);
+            }
+        }
+    }
+
+    #[test]
+    fn mutate_without_chances_changes_nothing() {
+        let [drawing, _] = parents();
+        let mutation = Mutation {
+            jitter: 0_f64,
+            remove: 0_f64,
+            add: 0_f64,
+            style: 0_f64,
+        };
+        let mutated = mutate(&drawing, &mutation, &mut StdRng::seed_from_u64(3)).unwrap();
+        assert_eq!(mutated.lines(), drawing.lines());
+        assert_eq!(mutated.content_hash(), drawing.content_hash());
+    }
+
+    #[test]
+    fn jittered_points_stay_in_grid() {
+        let [drawing, _] = parents();
+        let mut rng = StdRng::seed_from_u64(4);
+        for point in drawing.subgrid_points() {
+            for _ in 0..10 {
+                assert!(drawing.contains(jitter(&drawing, point, &mut rng)));
+            }
+        }
+    }
+}
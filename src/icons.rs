@@ -33,3 +33,51 @@ pub fn remove<Msg>() -> Node<Msg> {
         }]
     ]
 }
+
+pub fn duplicate<Msg>() -> Node<Msg> {
+    svg![
+        attrs! {
+            At::ViewBox => "0 0 24 24",
+            At::Fill => "none",
+            At::Stroke => "currentColor"
+        },
+        path![attrs! {
+            At::StrokeLinecap => "round",
+            At::StrokeLineJoin => "round",
+            At::StrokeWidth => 2,
+            At::D => "M8 16H6a2 2 0 01-2-2V6a2 2 0 012-2h8a2 2 0 012 2v2m-6 12h8a2 2 0 002-2v-8a2 2 0 00-2-2h-8a2 2 0 00-2 2v8a2 2 0 002 2z"
+        }]
+    ]
+}
+
+pub fn remix<Msg>() -> Node<Msg> {
+    svg![
+        attrs! {
+            At::ViewBox => "0 0 24 24",
+            At::Fill => "none",
+            At::Stroke => "currentColor"
+        },
+        path![attrs! {
+            At::StrokeLinecap => "round",
+            At::StrokeLineJoin => "round",
+            At::StrokeWidth => 2,
+            At::D => "M4 4v5h.582m15.356 2A8.001 8.001 0 004.582 9m0 0H9m11 11v-5h-.581m0 0a8.003 8.003 0 01-15.357-2m15.357 2H15"
+        }]
+    ]
+}
+
+pub fn star<Msg>() -> Node<Msg> {
+    svg![
+        attrs! {
+            At::ViewBox => "0 0 24 24",
+            At::Fill => "none",
+            At::Stroke => "currentColor"
+        },
+        path![attrs! {
+            At::StrokeLinecap => "round",
+            At::StrokeLineJoin => "round",
+            At::StrokeWidth => 2,
+            At::D => "M11.049 2.927c.3-.921 1.603-.921 1.902 0l1.519 4.674a1 1 0 00.95.69h4.915c.969 0 1.371 1.24.588 1.81l-3.976 2.888a1 1 0 00-.363 1.118l1.518 4.674c.3.922-.755 1.688-1.538 1.118l-3.976-2.888a1 1 0 00-1.176 0l-3.976 2.888c-.783.57-1.838-.197-1.538-1.118l1.518-4.674a1 1 0 00-.363-1.118l-3.976-2.888c-.784-.57-.38-1.81.588-1.81h4.914a1 1 0 00.951-.69l1.519-4.674z"
+        }]
+    ]
+}
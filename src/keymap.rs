@@ -0,0 +1,223 @@
+use seed::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::storage::KEYMAP_KEY;
+use track_drawer::symmetry::{Symmetry, Transform};
+
+/// Things that can be done on the draw page from the keyboard or command palette
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Action {
+    NextRandomLine,
+    AddLine,
+    NextRow,
+    RemoveRow,
+    LineFromLastEndpoint,
+    ConfirmPointInput,
+    ClearPointInput,
+    DeletePointInput,
+    ToggleShowPoints,
+    Clear,
+    Save,
+    Download,
+    TogglePlacingPoints,
+    ToggleFrame,
+    AddLayer,
+    /// The layer actions below work on the active layer
+    RemoveLayer,
+    ToggleLayerVisible,
+    ToggleLayerLocked,
+    Transform(Transform),
+    Symmetry(Symmetry),
+}
+
+impl Action {
+    pub const ALL: [Action; 28] = [
+        Action::NextRandomLine,
+        Action::AddLine,
+        Action::NextRow,
+        Action::RemoveRow,
+        Action::LineFromLastEndpoint,
+        Action::ConfirmPointInput,
+        Action::ClearPointInput,
+        Action::DeletePointInput,
+        Action::ToggleShowPoints,
+        Action::Clear,
+        Action::Save,
+        Action::Download,
+        Action::TogglePlacingPoints,
+        Action::ToggleFrame,
+        Action::AddLayer,
+        Action::RemoveLayer,
+        Action::ToggleLayerVisible,
+        Action::ToggleLayerLocked,
+        Action::Transform(Transform::FlipHorizontal),
+        Action::Transform(Transform::FlipVertical),
+        Action::Transform(Transform::Rotate180),
+        Action::Transform(Transform::Transpose),
+        Action::Symmetry(Symmetry::None),
+        Action::Symmetry(Symmetry::Horizontal),
+        Action::Symmetry(Symmetry::Vertical),
+        Action::Symmetry(Symmetry::Both),
+        Action::Symmetry(Symmetry::Rotational2),
+        Action::Symmetry(Symmetry::Rotational4),
+    ];
+
+    pub fn description(&self) -> String {
+        let description = match self {
+            Action::NextRandomLine => "Random line",
+            Action::AddLine => "Adds the last random line",
+            Action::NextRow => "New row",
+            Action::RemoveRow => "Remove the last row",
+            Action::LineFromLastEndpoint => "Random line from the end of the last line",
+            Action::ConfirmPointInput => "Random line from the typed point",
+            Action::ClearPointInput => "Clears the typed point",
+            Action::DeletePointInput => "Deletes the last typed character",
+            Action::ToggleShowPoints => "Show or hide points",
+            Action::Clear => "Clear all",
+            Action::Save => "Save",
+            Action::Download => "Export & download",
+            Action::TogglePlacingPoints => "Start or stop placing custom points",
+            Action::ToggleFrame => "Show or hide the frame",
+            Action::AddLayer => "New layer",
+            Action::RemoveLayer => "Remove the active layer",
+            Action::ToggleLayerVisible => "Show or hide the active layer",
+            Action::ToggleLayerLocked => "Lock or unlock the active layer",
+            Action::Transform(transform) => transform.name(),
+            Action::Symmetry(symmetry) => return format!("Symmetry: {}", symmetry.name()),
+        };
+        description.to_string()
+    }
+}
+
+/// Keys used for typing point numbers, which can't be bound to an action
+pub fn is_point_input_key(key: &str) -> bool {
+    key.len() == 1 && key.chars().all(|c| c.is_ascii_digit() || c == ',')
+}
+
+/// Keys the point input relies on, which can't be given to another action or taken from
+/// the one they're bound to
+pub fn is_reserved_key(key: &str) -> bool {
+    is_point_input_key(key) || matches!(key, "Enter" | "Escape" | "Backspace")
+}
+
+/// Human readable name for a key, as reported by `KeyboardEvent.key`
+pub fn key_name(key: &str) -> &str {
+    match key {
+        " " => "Space",
+        "Escape" => "Esc",
+        key => key,
+    }
+}
+
+/// The keys bound to each `Action`, saved so users can rebind them
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Keymap {
+    bindings: Vec<(String, Action)>,
+}
+
+impl Keymap {
+    pub fn load() -> Keymap {
+        let mut keymap: Keymap = LocalStorage::get(KEYMAP_KEY).unwrap_or_default();
+        // Backspace cleared the whole typed point before it could delete a character
+        for (key, action) in &mut keymap.bindings {
+            if key == "Backspace" && *action == Action::ClearPointInput {
+                *action = Action::DeletePointInput;
+            }
+        }
+        keymap
+    }
+
+    pub fn save(&self) {
+        LocalStorage::insert(KEYMAP_KEY, self).expect("Saving key bindings failed")
+    }
+
+    pub fn action(&self, key: &str) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| bound == key)
+            .map(|(_, action)| *action)
+    }
+
+    pub fn keys(&self, action: Action) -> impl Iterator<Item = &str> {
+        self.bindings
+            .iter()
+            .filter(move |(_, bound)| *bound == action)
+            .map(|(key, _)| key.as_str())
+    }
+
+    /// Binds `key` to `action` in place of `replacing`, one of the action's keys, or alongside
+    /// its other keys, taking `key` away from any other action. Returns whether it was bound,
+    /// reserved keys never being rebound
+    pub fn bind(&mut self, key: String, action: Action, replacing: Option<&str>) -> bool {
+        if replacing == Some(key.as_str()) {
+            return true;
+        }
+        if is_reserved_key(&key) || replacing.is_some_and(is_reserved_key) {
+            return false;
+        }
+        self.bindings.retain(|(bound_key, bound_action)| {
+            *bound_key != key && !(*bound_action == action && Some(bound_key.as_str()) == replacing)
+        });
+        self.bindings.push((key, action));
+        true
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap {
+            bindings: vec![
+                ("r".to_string(), Action::NextRandomLine),
+                ("c".to_string(), Action::AddLine),
+                ("n".to_string(), Action::NextRow),
+                ("x".to_string(), Action::RemoveRow),
+                ("l".to_string(), Action::LineFromLastEndpoint),
+                ("Enter".to_string(), Action::ConfirmPointInput),
+                ("Escape".to_string(), Action::ClearPointInput),
+                ("Backspace".to_string(), Action::DeletePointInput),
+                ("p".to_string(), Action::ToggleShowPoints),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(keymap: &Keymap, action: Action) -> Vec<&str> {
+        keymap.keys(action).collect()
+    }
+
+    #[test]
+    fn replaces_only_the_edited_key() {
+        let mut keymap = Keymap::default();
+        assert!(keymap.bind("a".to_string(), Action::NextRandomLine, None));
+        assert_eq!(keys(&keymap, Action::NextRandomLine), vec!["r", "a"]);
+        assert!(keymap.bind("b".to_string(), Action::NextRandomLine, Some("r")));
+        assert_eq!(keys(&keymap, Action::NextRandomLine), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn takes_keys_from_other_actions() {
+        let mut keymap = Keymap::default();
+        assert!(keymap.bind("c".to_string(), Action::NextRandomLine, Some("r")));
+        assert_eq!(keymap.action("c"), Some(Action::NextRandomLine));
+        assert!(keys(&keymap, Action::AddLine).is_empty());
+        assert_eq!(keymap.action("r"), None);
+    }
+
+    #[test]
+    fn keeps_point_input_keys() {
+        let mut keymap = Keymap::default();
+        for key in ["1", ",", "Enter", "Escape", "Backspace"] {
+            assert!(!keymap.bind(key.to_string(), Action::Save, None));
+        }
+        assert!(!keymap.bind("s".to_string(), Action::ConfirmPointInput, Some("Enter")));
+        assert!(keys(&keymap, Action::Save).is_empty());
+        assert_eq!(keys(&keymap, Action::ConfirmPointInput), vec!["Enter"]);
+        // Point input actions can still be given more keys
+        assert!(keymap.bind("s".to_string(), Action::ConfirmPointInput, None));
+        assert_eq!(keys(&keymap, Action::ConfirmPointInput), vec!["Enter", "s"]);
+    }
+}
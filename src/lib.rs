@@ -0,0 +1,9 @@
+pub mod decoration;
+pub mod drawing;
+pub mod export;
+pub mod generate;
+pub mod marker;
+pub mod render;
+pub mod renderer;
+pub mod stats;
+pub mod symmetry;
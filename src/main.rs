@@ -1,13 +1,16 @@
 use seed::{prelude::*, *};
 
-mod drawing;
+mod export_dialog;
 mod icons;
+mod keymap;
 mod page;
+mod stats_panel;
 mod storage;
 mod utils;
 
 const DRAW: &str = "draw";
 const VIEW: &str = "view";
+const EXPLORE: &str = "explore";
 
 fn init(url: Url, orders: &mut impl Orders<Msg>) -> Model {
     let base_url = url.to_base_url();
@@ -16,7 +19,7 @@ fn init(url: Url, orders: &mut impl Orders<Msg>) -> Model {
         .notify(subs::UrlChanged(url));
     Model {
         base_url,
-        page: Page::Draw(page::draw::init(&mut orders.proxy(Msg::DrawMsg))),
+        page: Page::Draw(Box::new(page::draw::init(&mut orders.proxy(Msg::DrawMsg)))),
     }
 }
 
@@ -29,11 +32,16 @@ impl<'a> Urls<'a> {
     pub fn view(self) -> Url {
         self.base_url().add_path_part(VIEW)
     }
+
+    pub fn explore(self) -> Url {
+        self.base_url().add_path_part(EXPLORE)
+    }
 }
 
 enum Page {
-    Draw(page::draw::Model),
-    View(page::view::Model),
+    Draw(Box<page::draw::Model>),
+    View(Box<page::view::Model>),
+    Explore(page::explore::Model),
 }
 
 struct Model {
@@ -44,6 +52,7 @@ struct Model {
 enum Msg {
     DrawMsg(page::draw::Msg),
     ViewMsg(page::view::Msg),
+    ExploreMsg(page::explore::Msg),
 
     UrlChanged(subs::UrlChanged),
 }
@@ -60,21 +69,36 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                 page::view::update(msg, model, &mut orders.proxy(Msg::ViewMsg))
             }
         }
+        Msg::ExploreMsg(msg) => {
+            if let Page::Explore(model) = &mut model.page {
+                page::explore::update(msg, model, &mut orders.proxy(Msg::ExploreMsg))
+            }
+        }
         Msg::UrlChanged(subs::UrlChanged(mut url)) => {
             let new_page = match url.next_path_part() {
                 Some(VIEW) => {
                     if !matches!(model.page, Page::View(_)) {
-                        Some(Page::View(page::view::init(
+                        Some(Page::View(Box::new(page::view::init(
+                            &url,
                             &mut orders.proxy(Msg::ViewMsg),
-                        )))
+                        ))))
                     } else {
                         None
                     }
                 }
                 Some(DRAW) => {
                     if !matches!(model.page, Page::Draw(_)) {
-                        Some(Page::Draw(page::draw::init(
+                        Some(Page::Draw(Box::new(page::draw::init(
                             &mut orders.proxy(Msg::DrawMsg),
+                        ))))
+                    } else {
+                        None
+                    }
+                }
+                Some(EXPLORE) => {
+                    if !matches!(model.page, Page::Explore(_)) {
+                        Some(Page::Explore(page::explore::init(
+                            &mut orders.proxy(Msg::ExploreMsg),
                         )))
                     } else {
                         None
@@ -94,6 +118,7 @@ fn view(model: &Model) -> Node<Msg> {
     let inner = match &model.page {
         Page::Draw(model) => page::draw::view(model).map_msg(Msg::DrawMsg),
         Page::View(model) => page::view::view(model).map_msg(Msg::ViewMsg),
+        Page::Explore(model) => page::explore::view(model).map_msg(Msg::ExploreMsg),
     };
     div![C!["h-screen flex flex-col"], navbar_view(model), inner]
 }
@@ -130,6 +155,19 @@ fn navbar_view(model: &Model) -> Node<Msg> {
                 At::Disabled => matches!(model.page, Page::View(_)).as_at_value(),
             },
             span![C!["text-center tracking-wider"], "View"]
+        ],
+        a![
+            C!["h-full justify-center items-center flex px-2"],
+            if matches!(model.page, Page::Explore(_)) {
+                C![SELECTED_CLASSES]
+            } else {
+                C![UNSELECTED_CLASSES]
+            },
+            attrs! {
+                At::Href => Urls::new(&model.base_url).explore(),
+                At::Disabled => matches!(model.page, Page::Explore(_)).as_at_value(),
+            },
+            span![C!["text-center tracking-wider"], "Explore"]
         ]
     ]
 }
@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+
+use crate::drawing::POINT_RADIUS;
+use crate::render::{Colour, Shape};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum MarkerShape {
+    #[default]
+    Circle,
+    Square,
+    /// An unfilled circle
+    Ring,
+    Cross,
+    None,
+}
+
+impl MarkerShape {
+    pub const ALL: [MarkerShape; 5] = [
+        MarkerShape::Circle,
+        MarkerShape::Square,
+        MarkerShape::Ring,
+        MarkerShape::Cross,
+        MarkerShape::None,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MarkerShape::Circle => "Circle",
+            MarkerShape::Square => "Square",
+            MarkerShape::Ring => "Ring",
+            MarkerShape::Cross => "Cross",
+            MarkerShape::None => "None",
+        }
+    }
+}
+
+/// How a grid point is marked
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(default)]
+pub struct Marker {
+    pub shape: MarkerShape,
+    /// Radius of a circle or ring, or half the width of a square or cross
+    pub size: f64,
+    /// Fill of circles & squares, following the lines if `None`
+    #[serde(alias = "colour")]
+    pub fill: Option<Colour>,
+    /// Stroke of rings & crosses, following the lines if `None`, & the outline of circles &
+    /// squares, which only have one if it's set
+    pub stroke: Option<Colour>,
+    pub stroke_width: f64,
+}
+
+impl Default for Marker {
+    fn default() -> Self {
+        Marker {
+            shape: MarkerShape::Circle,
+            size: POINT_RADIUS,
+            fill: None,
+            stroke: None,
+            stroke_width: 6_f64,
+        }
+    }
+}
+
+impl Marker {
+    /// Shapes marking each of `centres`, left for the enclosing group to colour unless the
+    /// marker has colours of its own
+    pub fn shapes(&self, centres: Vec<(f64, f64)>) -> Vec<Shape> {
+        if centres.is_empty() || self.size <= 0_f64 {
+            return vec![];
+        }
+        let size = self.size;
+        match self.shape {
+            MarkerShape::None => vec![],
+            MarkerShape::Circle => {
+                let mut shapes = coloured(
+                    self.fill,
+                    vec![Shape::Dots {
+                        centres: centres.clone(),
+                        radius: size,
+                    }],
+                );
+                shapes.extend(self.outline(self.rings(centres)));
+                shapes
+            }
+            MarkerShape::Square => {
+                let mut shapes = coloured(
+                    self.fill,
+                    centres
+                        .iter()
+                        .map(|centre| Shape::Square {
+                            centre: *centre,
+                            size,
+                        })
+                        .collect(),
+                );
+                shapes.extend(
+                    self.outline(
+                        centres
+                            .into_iter()
+                            .map(|(x, y)| Shape::Polygon {
+                                points: vec![
+                                    (x - size, y - size),
+                                    (x + size, y - size),
+                                    (x + size, y + size),
+                                    (x - size, y + size),
+                                ],
+                                width: self.stroke_width,
+                            })
+                            .collect(),
+                    ),
+                );
+                shapes
+            }
+            MarkerShape::Ring => coloured(self.stroke, self.rings(centres)),
+            MarkerShape::Cross => coloured(
+                self.stroke,
+                centres
+                    .into_iter()
+                    .flat_map(|(x, y)| {
+                        vec![
+                            Shape::Line {
+                                from: (x - size, y - size),
+                                to: (x + size, y + size),
+                                width: self.stroke_width,
+                            },
+                            Shape::Line {
+                                from: (x - size, y + size),
+                                to: (x + size, y - size),
+                                width: self.stroke_width,
+                            },
+                        ]
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    fn rings(&self, centres: Vec<(f64, f64)>) -> Vec<Shape> {
+        centres
+            .into_iter()
+            .map(|centre| Shape::Ring {
+                centre,
+                radius: self.size,
+                width: self.stroke_width,
+            })
+            .collect()
+    }
+
+    /// Outlines for filled shapes, only drawn in a stroke colour of their own
+    fn outline(&self, shapes: Vec<Shape>) -> Vec<Shape> {
+        match self.stroke {
+            Some(_) if self.stroke_width > 0_f64 => coloured(self.stroke, shapes),
+            _ => vec![],
+        }
+    }
+}
+
+/// Shapes in their own colour, or left for the enclosing group to colour
+fn coloured(colour: Option<Colour>, shapes: Vec<Shape>) -> Vec<Shape> {
+    match colour {
+        Some(colour) => vec![Shape::Group {
+            id: None,
+            colour,
+            shapes,
+        }],
+        None => shapes,
+    }
+}
+
+/// Markers for the three kinds of grid point
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(default)]
+pub struct Markers {
+    /// Points no line starts or ends at, drawn when the drawing includes its points
+    pub unused: Marker,
+    /// Points where one or two lines end
+    pub endpoint: Marker,
+    /// Points where three or more lines meet
+    pub station: Marker,
+}
+
+/// Lines ending at a point that make it a station
+pub const STATION_LINES: usize = 3;
@@ -1,19 +1,45 @@
 use rand::Rng;
 use seed::Attrs;
 use seed::{prelude::*, *};
-use web_sys::HtmlInputElement;
 
-use crate::drawing::Drawing;
-use crate::page::draw::Msg::LineFrom;
-use crate::storage::STORAGE_KEY;
+use crate::keymap::{self, Action, Keymap};
+use crate::storage::{self, REMIX_KEY};
 use crate::utils;
+use crate::{export_dialog, icons, stats_panel};
+use track_drawer::decoration::{Corners, Fill};
+use track_drawer::drawing::{
+    Canvas, Drawing, DrawingError, Line, Point, PointRef, Subgrid, CELL_STEPS, MAX_GRID_SIZE,
+    POINT_RADIUS,
+};
+use track_drawer::generate;
+use track_drawer::marker::{Marker, MarkerShape};
+use track_drawer::render::{self, Colour, Shape};
+use track_drawer::renderer::Renderer;
+use track_drawer::symmetry::{Symmetry, Transform};
 
 pub struct Model {
-    y_limits: (i16, i16),
-    next_line: Option<((i16, i16), (i16, i16))>,
+    next_line: Option<Line>,
     drawing: Drawing,
+    /// Partners every confirmed line gets
+    symmetry: Symmetry,
+    /// Whether clicking the canvas places custom points rather than drawing lines
+    placing_points: bool,
+    /// Where a line being drawn by clicking starts, once clicked
+    line_start: Option<Point>,
+    /// Digits typed so far for picking a grid point by number, as `column` or `column,row`
+    point_input: String,
+    error: Option<DrawingError>,
 
-    svg_ref: ElRef<web_sys::HtmlElement>,
+    keymap: Keymap,
+    /// The action waiting for a key press to be bound to it, & which of its keys is replaced
+    rebinding: Option<(Action, Option<String>)>,
+    /// Why the last key pressed to rebind an action wasn't bound
+    rebind_warning: Option<String>,
+    /// Filter text of the command palette, when open
+    palette: Option<String>,
+    palette_ref: ElRef<web_sys::HtmlInputElement>,
+
+    export: export_dialog::Model,
 
     #[allow(dead_code)]
     input_handle: StreamHandle, // Make sure we drop our stream when the user leave this page
@@ -22,99 +48,432 @@ pub struct Model {
 pub enum Msg {
     ToggleShowPoints,
     NextRandomLine,
-    LineFrom(u16),
+    LineFrom(PointRef),
     AddLine,
     NextRow,
+    RemoveRow,
+    PointInput(char),
+    ConfirmPointInput,
+    ClearPointInput,
+    DeletePointInput,
     Download,
+    Export(export_dialog::Msg),
     ChangeNumCols(u16),
+    ChangeNumRows(u16),
+    ChangeCanvas(Canvas),
+    ChangeMargin(f64),
+    ChangeSubgrid(Subgrid),
+    TogglePlacingPoints,
+    ClearCustomPoints,
+    /// A click on the canvas, in the units of its view box
+    CanvasClick((f64, f64)),
+    ChangeBackgroundFill(Fill),
+    ChangeBackgroundColour(Colour),
+    ChangeGradientColour(Colour),
+    ToggleFrame,
+    ChangeFrameColour(Colour),
+    ChangeFrameWidth(f64),
+    ChangeFrameInset(f64),
+    ChangeFrameCorners(Corners),
+    ChangeCornerSize(f64),
+    ChangeMarker(MarkerKind, Marker),
+    ChangeRenderer(Renderer),
+    ChangeSymmetry(Symmetry),
+    SelectLayer(usize),
+    AddLayer,
+    RemoveLayer(usize),
+    RenameLayer(usize, String),
+    ToggleLayerVisible(usize),
+    ToggleLayerLocked(usize),
+    ChangeLayerColour(usize, Colour),
+    ChangeLayerWidth(usize, f64),
+    Transform(Transform),
     Clear,
     Save,
+    KeyPressed(String),
+    /// Waits for a key to bind to the action, in place of the given key or as well as its others
+    StartRebind(Action, Option<String>),
+    ResetKeymap,
+    TogglePalette,
+    ChangePaletteFilter(String),
+    RunAction(Action),
+}
+
+/// The kinds of grid point that are marked differently
+#[derive(Clone, Copy)]
+pub enum MarkerKind {
+    Unused,
+    Endpoint,
+    Station,
 }
 
 pub fn init(orders: &mut impl Orders<Msg>) -> Model {
     let input_handle = orders.stream_with_handle(streams::window_event(Ev::KeyDown, |ev| {
         let ev: web_sys::KeyboardEvent = ev.unchecked_into();
-        if ev.key() == "c" {
-            Some(Msg::AddLine)
-        } else if ev.key() == "r" {
-            Some(Msg::NextRandomLine)
-        } else if ev.key() == "n" {
-            Some(Msg::NextRow)
+        if ev.ctrl_key() && ev.key() == "k" {
+            ev.prevent_default();
+            Some(Msg::TogglePalette)
+        } else if ev.ctrl_key()
+            || ev.alt_key()
+            || ev.meta_key()
+            || ev.key() == "Shift"
+            || is_typing(&ev)
+        {
+            None
         } else {
-            if let Some(num) = ev.key().parse().ok() {
-                Some(LineFrom(num))
-            } else {
-                None
-            }
+            Some(Msg::KeyPressed(ev.key()))
         }
     }));
     Model {
-        y_limits: (0, 2),
         next_line: None,
-        drawing: Drawing::new(),
-        svg_ref: ElRef::new(),
+        drawing: take_remix().unwrap_or_default(),
+        symmetry: Symmetry::default(),
+        placing_points: false,
+        line_start: None,
+        point_input: String::new(),
+        error: None,
+        keymap: Keymap::load(),
+        rebinding: None,
+        rebind_warning: None,
+        palette: None,
+        palette_ref: ElRef::new(),
+        export: export_dialog::Model::default(),
         input_handle,
     }
 }
 
-pub fn update(msg: Msg, model: &mut Model, _orders: &mut impl Orders<Msg>) {
+/// Takes a drawing the gallery left to be opened here, if any
+fn take_remix() -> Option<Drawing> {
+    let drawing = SessionStorage::get(REMIX_KEY).ok();
+    SessionStorage::remove(REMIX_KEY).ok();
+    drawing
+}
+
+/// Whether a key press is meant for a text field rather than a shortcut
+fn is_typing(ev: &web_sys::KeyboardEvent) -> bool {
+    let target = ev.target();
+    if let Some(input) = target
+        .as_ref()
+        .and_then(|target| target.dyn_ref::<web_sys::HtmlInputElement>())
+    {
+        !matches!(
+            input.type_().as_str(),
+            "checkbox" | "radio" | "range" | "button" | "submit"
+        )
+    } else {
+        target
+            .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+            .map(|el| matches!(el.tag_name().as_str(), "TEXTAREA" | "SELECT"))
+            .unwrap_or(false)
+    }
+}
+
+fn action_msg(model: &Model, action: Action) -> Msg {
+    let active_layer = model.drawing.active_layer();
+    match action {
+        Action::NextRandomLine => Msg::NextRandomLine,
+        Action::AddLine => Msg::AddLine,
+        Action::NextRow => Msg::NextRow,
+        Action::RemoveRow => Msg::RemoveRow,
+        Action::LineFromLastEndpoint => Msg::LineFrom(PointRef::LastEndpoint),
+        Action::ConfirmPointInput => Msg::ConfirmPointInput,
+        Action::ClearPointInput => Msg::ClearPointInput,
+        Action::DeletePointInput => Msg::DeletePointInput,
+        Action::ToggleShowPoints => Msg::ToggleShowPoints,
+        Action::Clear => Msg::Clear,
+        Action::Save => Msg::Save,
+        Action::Download => Msg::Download,
+        Action::TogglePlacingPoints => Msg::TogglePlacingPoints,
+        Action::ToggleFrame => Msg::ToggleFrame,
+        Action::AddLayer => Msg::AddLayer,
+        Action::RemoveLayer => Msg::RemoveLayer(active_layer),
+        Action::ToggleLayerVisible => Msg::ToggleLayerVisible(active_layer),
+        Action::ToggleLayerLocked => Msg::ToggleLayerLocked(active_layer),
+        Action::Transform(transform) => Msg::Transform(transform),
+        Action::Symmetry(symmetry) => Msg::ChangeSymmetry(symmetry),
+    }
+}
+
+/// Actions shown in the command palette for the given filter text
+fn palette_actions(filter: &str) -> impl Iterator<Item = Action> + '_ {
+    Action::ALL.iter().copied().filter(move |action| {
+        action
+            .description()
+            .to_lowercase()
+            .contains(&filter.to_lowercase())
+    })
+}
+
+impl Model {
+    /// The rows new lines are drawn between, being the last two rows of the grid
+    fn active_rows(&self) -> (i16, i16) {
+        let height = self.drawing.grid_height as i16;
+        ((height - 2).max(0), height)
+    }
+}
+
+pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
     let mut rng = rand::thread_rng();
+    let y_limits = model.active_rows();
     match msg {
         Msg::ToggleShowPoints => model.drawing.toggle_include_points(),
         Msg::NextRandomLine => {
-            let x_limits = (0 as i16, model.drawing.grid_width as i16);
-            model.next_line = Some((
-                (
-                    rng.gen_range(x_limits.0..x_limits.1),
-                    rng.gen_range(model.y_limits.0..model.y_limits.1),
-                ),
-                (
-                    rng.gen_range(x_limits.0..x_limits.1),
-                    rng.gen_range(model.y_limits.0..model.y_limits.1),
-                ),
-            ))
+            model.next_line = Some(generate::random_line(&mut rng, &model.drawing, y_limits));
+            model.line_start = None;
         }
         Msg::AddLine => {
-            if let Some((from, to)) = model.next_line {
-                model.drawing.add_line(from, to);
+            if let Some(line) = model.next_line {
+                let lines = model.symmetry.lines(&model.drawing, line);
+                // Lines ending at custom points need their partners to end at custom points too
+                let partner_points: Vec<Point> = [line.0, line.1]
+                    .iter()
+                    .filter(|end| model.drawing.custom_points().contains(end))
+                    .flat_map(|end| model.symmetry.points(&model.drawing, *end))
+                    .collect();
+                model.error = lines
+                    .and_then(|lines| model.drawing.add_lines(&lines))
+                    .and_then(|()| {
+                        partner_points
+                            .into_iter()
+                            .try_for_each(|point| model.drawing.add_custom_point(point))
+                    })
+                    .err();
                 model.next_line = None;
+                model.line_start = None;
             }
         }
         Msg::NextRow => {
-            model.drawing.grid_height += 1;
-            model.y_limits = (model.y_limits.0 + 1, model.y_limits.1 + 1);
+            model.drawing.add_row();
+        }
+        Msg::RemoveRow => {
+            model.error = model.drawing.remove_row().err();
+            model.next_line = None;
         }
-        Msg::LineFrom(x) => {
-            if x <= model.drawing.grid_width {
+        Msg::LineFrom(point) => match model.drawing.resolve(point) {
+            Ok(from) => {
+                model.error = None;
                 model.next_line = Some((
-                    ((x as i16) - 1, model.y_limits.0),
-                    (
-                        rng.gen_range(0_i16..model.drawing.grid_width as i16),
-                        rng.gen_range(model.y_limits.0..model.y_limits.1),
-                    ),
+                    from,
+                    generate::random_point(&mut rng, &model.drawing, y_limits),
                 ))
             }
+            Err(err) => model.error = Some(err),
+        },
+        Msg::PointInput(c) => {
+            model.point_input.push(c);
+        }
+        Msg::ConfirmPointInput => {
+            if let Some(point) = parse_point_input(&model.point_input) {
+                orders.send_msg(Msg::LineFrom(point));
+            }
+            model.point_input.clear();
+        }
+        Msg::ClearPointInput => {
+            model.point_input.clear();
+        }
+        Msg::DeletePointInput => {
+            model.point_input.pop();
         }
         Msg::Download => {
-            utils::download_svg(&model.svg_ref);
+            orders.send_msg(Msg::Export(export_dialog::Msg::Open));
         }
+        Msg::Export(msg) => export_dialog::update(msg, &mut model.export, &[&model.drawing]),
         Msg::ChangeNumCols(x) => {
-            model.drawing.grid_width = x;
+            model.error = model.drawing.set_grid_width(x).err();
+            if let Some((from, to)) = model.next_line {
+                if !model.drawing.contains(from) || !model.drawing.contains(to) {
+                    model.next_line = None;
+                }
+            }
+        }
+        Msg::ChangeNumRows(y) => {
+            model.error = model.drawing.set_grid_height(y).err();
+            model.next_line = None;
+        }
+        Msg::ChangeCanvas(canvas) => {
+            model.drawing.set_canvas(canvas);
+        }
+        Msg::ChangeMargin(margin) => {
+            model.drawing.set_margin(margin);
+        }
+        Msg::ChangeSubgrid(subgrid) => model.drawing.subgrid = subgrid,
+        Msg::TogglePlacingPoints => {
+            model.placing_points = !model.placing_points;
+            model.line_start = None;
+        }
+        Msg::ClearCustomPoints => model.drawing.clear_custom_points(),
+        Msg::CanvasClick(position) => {
+            if model.placing_points {
+                let point = model.drawing.point_at(position);
+                model.error = model.drawing.add_custom_point(point).err();
+            } else {
+                let point = model.drawing.nearest_snap_point(position);
+                // The first click shows where the line starts, the second where it ends
+                model.next_line = Some((model.line_start.unwrap_or(point), point));
+                model.line_start = match model.line_start {
+                    Some(_) => None,
+                    None => Some(point),
+                };
+                model.error = None;
+            }
+        }
+        Msg::ChangeBackgroundFill(fill) => model.drawing.background.fill = fill,
+        Msg::ChangeBackgroundColour(colour) => model.drawing.background.colour = colour,
+        Msg::ChangeGradientColour(colour) => model.drawing.background.gradient_to = colour,
+        Msg::ToggleFrame => model.drawing.frame.shown = !model.drawing.frame.shown,
+        Msg::ChangeFrameColour(colour) => model.drawing.frame.colour = colour,
+        Msg::ChangeFrameWidth(width) => model.drawing.frame.width = width.max(1_f64),
+        Msg::ChangeFrameInset(inset) => model.drawing.frame.inset = inset.max(0_f64),
+        Msg::ChangeFrameCorners(corners) => model.drawing.frame.corners = corners,
+        Msg::ChangeCornerSize(size) => model.drawing.frame.corner_size = size.max(0_f64),
+        Msg::ChangeMarker(kind, marker) => {
+            let markers = &mut model.drawing.markers;
+            let changed = match kind {
+                MarkerKind::Unused => &mut markers.unused,
+                MarkerKind::Endpoint => &mut markers.endpoint,
+                MarkerKind::Station => &mut markers.station,
+            };
+            *changed = Marker {
+                size: marker.size.max(0_f64),
+                stroke_width: marker.stroke_width.max(0_f64),
+                ..marker
+            };
+        }
+        Msg::ChangeRenderer(renderer) => {
+            model.drawing.renderer = renderer;
+            // Sketched lines wobble by the seed, so they look the same each time they're drawn
+            if renderer == Renderer::Sketch {
+                model.drawing.seed.get_or_insert_with(|| rng.gen());
+            }
+        }
+        Msg::ChangeSymmetry(symmetry) => {
+            // The select disables symmetries that don't fit, but the palette lists them all
+            if symmetry.fits(&model.drawing) {
+                model.symmetry = symmetry;
+                model.error = None;
+            } else {
+                model.error = Some(DrawingError::NotSquare);
+            }
+        }
+        Msg::SelectLayer(idx) => {
+            model.drawing.set_active_layer(idx);
+            model.error = None;
+        }
+        Msg::AddLayer => {
+            model.drawing.add_layer();
+            model.error = None;
+        }
+        Msg::RemoveLayer(idx) => {
+            model.drawing.remove_layer(idx);
+            model.next_line = None;
+        }
+        Msg::RenameLayer(idx, name) => {
+            if let Some(layer) = model.drawing.layer_mut(idx) {
+                layer.name = name;
+            }
+        }
+        Msg::ToggleLayerVisible(idx) => {
+            if let Some(layer) = model.drawing.layer_mut(idx) {
+                layer.visible = !layer.visible;
+            }
+        }
+        Msg::ToggleLayerLocked(idx) => {
+            if let Some(layer) = model.drawing.layer_mut(idx) {
+                layer.locked = !layer.locked;
+            }
+        }
+        Msg::ChangeLayerColour(idx, colour) => {
+            if let Some(layer) = model.drawing.layer_mut(idx) {
+                layer.colour = colour;
+            }
+        }
+        Msg::ChangeLayerWidth(idx, width) => {
+            if let Some(layer) = model.drawing.layer_mut(idx) {
+                layer.line_width = width.max(1_f64);
+            }
+        }
+        Msg::Transform(transform) => {
+            model.error = transform.apply(&mut model.drawing).err();
+            model.next_line = None;
         }
         Msg::Clear => {
-            model.y_limits = (0, 2);
             model.drawing = Drawing::new();
+            model.line_start = None;
+            model.error = None;
         }
         Msg::Save => {
-            let mut saved_drawings: Vec<Drawing> =
-                LocalStorage::get(STORAGE_KEY).unwrap_or(Vec::new());
-            saved_drawings.push(std::mem::replace(&mut model.drawing, Drawing::new()));
+            let mut saved_drawings = storage::load_drawings();
+            let mut drawing = std::mem::replace(&mut model.drawing, Drawing::new());
+            if drawing.title.is_empty() {
+                drawing.title = storage::default_title(&saved_drawings);
+            }
+            drawing.created = Some(utils::now());
+            drawing.modified = drawing.created.clone();
+            saved_drawings.push(drawing);
             model.next_line = None;
-            LocalStorage::insert(STORAGE_KEY, &saved_drawings).expect("Saving drawing failed")
+            storage::save_drawings(&saved_drawings);
+        }
+        Msg::KeyPressed(key) => {
+            if let Some((action, replacing)) = model.rebinding.take() {
+                if key == "Escape" {
+                    model.rebind_warning = None;
+                } else if model.keymap.bind(key.clone(), action, replacing.as_deref()) {
+                    model.rebind_warning = None;
+                    model.keymap.save();
+                } else {
+                    model.rebind_warning = Some(format!(
+                        "{} is kept for typing points",
+                        keymap::key_name(replacing.as_deref().unwrap_or(&key))
+                    ));
+                }
+            } else if let Some(action) = model.keymap.action(&key) {
+                orders.send_msg(action_msg(model, action));
+            } else if keymap::is_point_input_key(&key) {
+                orders.send_msg(Msg::PointInput(key.chars().next().unwrap()));
+            }
+        }
+        Msg::StartRebind(action, replacing) => {
+            model.rebinding = Some((action, replacing));
+        }
+        Msg::ResetKeymap => {
+            model.keymap = Keymap::default();
+            model.rebind_warning = None;
+            model.keymap.save();
+        }
+        Msg::TogglePalette => {
+            if model.palette.take().is_none() {
+                model.palette = Some(String::new());
+                let palette_ref = model.palette_ref.clone();
+                orders.after_next_render(move |_| {
+                    if let Some(input) = palette_ref.get() {
+                        input.focus().ok();
+                    }
+                });
+            }
+        }
+        Msg::ChangePaletteFilter(filter) => {
+            model.palette = Some(filter);
+        }
+        Msg::RunAction(action) => {
+            model.palette = None;
+            orders.send_msg(action_msg(model, action));
         }
     }
 }
 
+/// Parses typed point input, either a column on the previous row or a `column,row` pair
+fn parse_point_input(input: &str) -> Option<PointRef> {
+    let mut parts = input.split(',');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(column), None, None) => column.parse().ok().map(PointRef::PreviousRow),
+        (Some(column), Some(row), None) => Some(PointRef::At {
+            column: column.parse().ok()?,
+            row: row.parse().ok()?,
+        }),
+        _ => None,
+    }
+}
+
 fn button_class(disabled: bool) -> Attrs {
     C![
         "py-2 px-4 w-1/2 bg-blue-500 text-white font-semibold rounded-md shadow-md",
@@ -131,7 +490,98 @@ pub fn view(model: &Model) -> Node<Msg> {
     div![
         C!["flex flex-grow flex-row w-screen"],
         sidebar_view(model),
-        svg_view(model)
+        svg_view(model),
+        model
+            .palette
+            .as_ref()
+            .map(|filter| palette_view(model, filter)),
+        export_dialog::view(&model.export, &[&model.drawing]).map(|node| node.map_msg(Msg::Export))
+    ]
+}
+
+fn shortcut_view(model: &Model, action: Action) -> Vec<Node<Msg>> {
+    let rebinding = |replacing: Option<&str>| {
+        model
+            .rebinding
+            .as_ref()
+            .is_some_and(|(rebound, key)| *rebound == action && key.as_deref() == replacing)
+    };
+    let key_button = |replacing: Option<&str>| {
+        let text = if rebinding(replacing) {
+            "Press a key...".to_string()
+        } else {
+            replacing.map(keymap::key_name).unwrap_or("+").to_string()
+        };
+        let replacing = replacing.map(str::to_string);
+        button![
+            C!["px-1 rounded hover:bg-blue-200 focus:outline-none"],
+            text,
+            attrs! {
+                At::Title => if replacing.is_some() { "Click to change" } else { "Click to add a key" }
+            },
+            ev(Ev::Click, move |_| Msg::StartRebind(action, replacing))
+        ]
+    };
+    vec![
+        dt![
+            model.keymap.keys(action).map(|key| {
+                if keymap::is_reserved_key(key) {
+                    span![
+                        C!["px-1"],
+                        attrs! {At::Title => "Kept for typing points"},
+                        keymap::key_name(key)
+                    ]
+                } else {
+                    key_button(Some(key))
+                }
+            }),
+            key_button(None)
+        ],
+        dd![action.description()],
+    ]
+}
+
+fn palette_view(model: &Model, filter: &str) -> Node<Msg> {
+    let first = palette_actions(filter).next();
+    div![
+        C!["fixed inset-0 bg-gray-900 bg-opacity-50 flex justify-center items-start pt-32"],
+        ev(Ev::Click, |_| Msg::TogglePalette),
+        div![
+            C!["w-1/3 bg-white rounded-md shadow-lg p-2"],
+            ev(Ev::Click, |e| e.stop_propagation()),
+            input![
+                C!["form-input w-full"],
+                el_ref(&model.palette_ref),
+                attrs! {
+                    At::Type => "text",
+                    At::Placeholder => "Type a command...",
+                    At::Value => filter
+                },
+                input_ev(Ev::Input, Msg::ChangePaletteFilter),
+                keyboard_ev(Ev::KeyDown, move |ev| match ev.key().as_str() {
+                    "Escape" => Some(Msg::TogglePalette),
+                    "Enter" => first.map(Msg::RunAction),
+                    _ => None,
+                })
+            ],
+            ul![
+                C!["mt-2"],
+                palette_actions(filter).map(|action| li![
+                    C!["flex justify-between px-2 py-1 rounded cursor-pointer hover:bg-blue-100"],
+                    span![action.description()],
+                    span![
+                        C!["text-gray-500"],
+                        model
+                            .keymap
+                            .keys(action)
+                            .map(keymap::key_name)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ],
+                    ev(Ev::Click, move |_| Msg::RunAction(action))
+                ])
+            ]
+        ]
     ]
 }
 
@@ -173,12 +623,19 @@ fn sidebar_view(model: &Model) -> Node<Msg> {
                 attrs! {At::Disabled => model.next_line.is_some().as_at_value()},
                 ev(Ev::Click, |_| Msg::NextRow),
             ],
+            button![
+                "Remove Row",
+                button_class(model.drawing.grid_height <= 1),
+                attrs! {At::Disabled => (model.drawing.grid_height <= 1).as_at_value()},
+                ev(Ev::Click, |_| Msg::RemoveRow),
+            ],
             button![
                 "Clear All",
                 button_class(false),
                 ev(Ev::Click, |_| Msg::Clear)
             ],
         ],
+        layers_view(model),
         div![
         C!["py-2"],
             div![
@@ -186,46 +643,78 @@ fn sidebar_view(model: &Model) -> Node<Msg> {
                 "Keyboard Shortcuts",
                 dl![
                     C!["grid grid-cols-2 gap-2 text-sm my-3 font-light"],
-                    dt!["r"],
-                    dd!["Random line"],
-                    dt!["n"],
-                    dd!["New row"],
-                    dt!["c"],
-                    dd!["Adds the last random line"],
-                    dt!["0..9"],
-                    dd!["Random line from numbered point"]
-                ]
+                    Action::ALL.iter().map(|action| shortcut_view(model, *action)),
+                    dt!["0..9, Enter"],
+                    dd!["Random line from numbered point on the previous row"],
+                    dt!["0..9 , 0..9, Enter"],
+                    dd!["Random line from column, row"],
+                    dt!["Ctrl+K"],
+                    dd!["Command palette"]
+                ],
+                model
+                    .rebind_warning
+                    .as_ref()
+                    .map(|warning| div![C!["text-sm text-red-600 mb-2"], warning]),
+                button![
+                    C!["text-xs underline focus:outline-none"],
+                    "Reset shortcuts",
+                    ev(Ev::Click, |_| Msg::ResetKeymap)
+                ],
+                IF!(!model.point_input.is_empty() => div![
+                    C!["text-sm text-blue-800"],
+                    format!("Point: {}", model.point_input)
+                ]),
+                model.error.map(|err| div![C!["text-sm text-red-600"], err.to_string()])
             ]
         ],
         div![
             C!["p-2 w-full flex flex-col items-center my-2"],
-            label![
-                C!["text-left mb-1 w-full"],
-                "Grid width:"
+            label![C!["text-left mb-1 w-full"], "Grid width:"],
+            grid_size_input(model.drawing.grid_width, Msg::ChangeNumCols),
+            label![C!["text-left mt-2 mb-1 w-full"], "Grid height:"],
+            grid_size_input(model.drawing.grid_height, Msg::ChangeNumRows),
+        ],
+        points_view(model),
+        div![
+            C!["p-2 w-full flex flex-col items-center my-2"],
+            label![C!["text-left mb-1 w-full"], "Canvas:"],
+            select![
+                C!["form-select w-full"],
+                Canvas::ALL.iter().enumerate().map(|(idx, canvas)| option![
+                    attrs! {
+                        At::Value => idx,
+                        At::Selected => (*canvas == model.drawing.canvas()).as_at_value()
+                    },
+                    canvas.name()
+                ]),
+                input_ev(Ev::Change, |value| {
+                    value
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|idx| Canvas::ALL.get(idx))
+                        .map(|canvas| Msg::ChangeCanvas(*canvas))
+                })
             ],
+            label![C!["text-left mt-2 mb-1 w-full"], "Margin:"],
             input![
-                C!["w-full"],
-                attrs!{
-                At::Type => "range",
-                At::Min => 1,
-                At::Max => 8,
-                At::Step => 1,
-                At::Value => model.drawing.grid_width
+                C!["form-input w-full"],
+                attrs! {
+                    At::Type => "number",
+                    At::Min => 0,
+                    At::Step => 10,
+                    At::Value => model.drawing.margin()
                 },
-                ev(Ev::Change, |change| {
-                    let input_elem: HtmlInputElement = change.target().unwrap().dyn_into().unwrap();
-                    Msg::ChangeNumCols(input_elem.value().parse().unwrap())
-                })
-            ],
-            div![
-                C!["flex justify-between mt-2 text-xs text-gray-600 w-full px-1"],
-                span![C!["text-left"], format!("{}", 1)],
-                (2..8)
-                    .into_iter()
-                    .map(|i| span![C!["text-center left-2"], format!("{}", i)]),
-                span![C!["text-right"], format!("{}", 8)]
+                input_ev(Ev::Change, |value| value.parse().ok().map(Msg::ChangeMargin))
             ]
         ],
+        decoration_view(model),
+        markers_view(model),
+        symmetry_view(model),
+        div![
+            C!["p-2 w-full flex flex-col my-2"],
+            label![C!["text-left mb-1 w-full"], "Stats:"],
+            stats_panel::view(&model.drawing)
+        ],
         div![
             C!["pt-2 items-center flex flex-col w-full"],
             button!["Save", button_class(false), ev(Ev::Click, |_| Msg::Save)],
@@ -234,7 +723,510 @@ fn sidebar_view(model: &Model) -> Node<Msg> {
     ]
 }
 
+fn points_view(model: &Model) -> Node<Msg> {
+    let custom_points = model.drawing.custom_points().len();
+    div![
+        C!["p-2 w-full flex flex-col items-center my-2"],
+        label![C!["text-left mb-1 w-full"], "Snap to:"],
+        select![
+            C!["form-select w-full"],
+            Subgrid::ALL
+                .iter()
+                .enumerate()
+                .map(|(idx, subgrid)| option![
+                    attrs! {
+                        At::Value => idx,
+                        At::Selected => (*subgrid == model.drawing.subgrid).as_at_value()
+                    },
+                    subgrid.name()
+                ]),
+            input_ev(Ev::Change, |value| {
+                value
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|idx| Subgrid::ALL.get(idx))
+                    .map(|subgrid| Msg::ChangeSubgrid(*subgrid))
+            })
+        ],
+        label![
+            C!["flex items-center w-full mt-2"],
+            "Place custom points",
+            input![
+                C!["form-checkbox ml-2"],
+                attrs! {
+                    At::Type => "checkbox",
+                    At::Checked => model.placing_points.as_at_value()
+                },
+                ev(Ev::Click, |_| Msg::TogglePlacingPoints)
+            ]
+        ],
+        div![
+            C!["text-sm text-gray-500 w-full"],
+            if model.placing_points {
+                "Click the canvas to add a point"
+            } else {
+                "Click two points to draw a line"
+            }
+        ],
+        button![
+            C!["text-xs underline focus:outline-none mt-1"],
+            IF!(custom_points == 0 => C!["opacity-50 cursor-default"]),
+            format!("Clear {} custom points", custom_points),
+            attrs! {At::Disabled => (custom_points == 0).as_at_value()},
+            ev(Ev::Click, |_| Msg::ClearCustomPoints)
+        ]
+    ]
+}
+
+fn layers_view(model: &Model) -> Node<Msg> {
+    let removable = model.drawing.layers().len() > 1;
+    div![
+        C!["p-2 w-full flex flex-col my-2"],
+        label![C!["text-left mb-1 w-full"], "Layers:"],
+        model
+            .drawing
+            .layers()
+            .iter()
+            .enumerate()
+            .map(|(idx, layer)| {
+                div![
+                    C![
+                        "flex flex-row items-center space-x-1 py-1",
+                        IF!(idx == model.drawing.active_layer() => "bg-blue-100")
+                    ],
+                    input![
+                        C!["form-radio"],
+                        attrs! {
+                            At::Type => "radio",
+                            At::Name => "active-layer",
+                            At::Title => "Draw on this layer",
+                            At::Checked => (idx == model.drawing.active_layer()).as_at_value()
+                        },
+                        ev(Ev::Click, move |_| Msg::SelectLayer(idx))
+                    ],
+                    input![
+                        C!["form-input w-full py-1 px-2 text-sm"],
+                        attrs! {At::Value => layer.name},
+                        input_ev(Ev::Change, move |name| Msg::RenameLayer(idx, name))
+                    ],
+                    input![
+                        C!["form-checkbox"],
+                        attrs! {
+                            At::Type => "checkbox",
+                            At::Title => "Visible",
+                            At::Checked => layer.visible.as_at_value()
+                        },
+                        ev(Ev::Click, move |_| Msg::ToggleLayerVisible(idx))
+                    ],
+                    input![
+                        C!["form-checkbox"],
+                        attrs! {
+                            At::Type => "checkbox",
+                            At::Title => "Locked",
+                            At::Checked => layer.locked.as_at_value()
+                        },
+                        ev(Ev::Click, move |_| Msg::ToggleLayerLocked(idx))
+                    ],
+                    input![
+                        C!["w-6 flex-shrink-0"],
+                        attrs! {
+                            At::Type => "color",
+                            At::Title => "Colour",
+                            At::Value => layer.colour.to_hex()
+                        },
+                        input_ev(Ev::Input, move |value| Colour::from_hex(&value)
+                            .map(|colour| Msg::ChangeLayerColour(idx, colour)))
+                    ],
+                    input![
+                        C!["form-input w-16 py-1 px-1 text-sm"],
+                        attrs! {
+                            At::Type => "number",
+                            At::Title => "Line width",
+                            At::Min => 1,
+                            At::Step => 5,
+                            At::Value => layer.line_width
+                        },
+                        input_ev(Ev::Change, move |value| value
+                            .parse()
+                            .ok()
+                            .map(|width| Msg::ChangeLayerWidth(idx, width)))
+                    ],
+                    if removable {
+                        button![
+                            C!["w-6 flex-shrink-0 stroke-current text-red-500 opacity-25 hover:opacity-100 focus:outline-none"],
+                            icons::remove(),
+                            attrs! {At::Title => "Remove layer"},
+                            ev(Ev::Click, move |_| Msg::RemoveLayer(idx))
+                        ]
+                    } else {
+                        empty![]
+                    }
+                ]
+            }),
+        button![
+            C!["self-center"],
+            "Add Layer",
+            button_class(false),
+            ev(Ev::Click, |_| Msg::AddLayer)
+        ]
+    ]
+}
+
+fn decoration_view(model: &Model) -> Node<Msg> {
+    let background = &model.drawing.background;
+    let frame = &model.drawing.frame;
+    div![
+        C!["p-2 w-full flex flex-col items-center my-2"],
+        label![C!["text-left mb-1 w-full"], "Style:"],
+        select![
+            C!["form-select w-full mb-2"],
+            Renderer::ALL
+                .iter()
+                .enumerate()
+                .map(|(idx, renderer)| option![
+                    attrs! {
+                        At::Value => idx,
+                        At::Selected => (*renderer == model.drawing.renderer).as_at_value()
+                    },
+                    renderer.name()
+                ]),
+            input_ev(Ev::Change, |value| {
+                value
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|idx| Renderer::ALL.get(idx))
+                    .map(|renderer| Msg::ChangeRenderer(*renderer))
+            })
+        ],
+        label![C!["text-left mb-1 w-full"], "Background:"],
+        div![
+            C!["flex flex-row items-center w-full space-x-2"],
+            select![
+                C!["form-select w-full"],
+                Fill::ALL.iter().enumerate().map(|(idx, fill)| option![
+                    attrs! {
+                        At::Value => idx,
+                        At::Selected => (*fill == background.fill).as_at_value()
+                    },
+                    fill.name()
+                ]),
+                input_ev(Ev::Change, |value| {
+                    value
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|idx| Fill::ALL.get(idx))
+                        .map(|fill| Msg::ChangeBackgroundFill(*fill))
+                })
+            ],
+            if background.fill != Fill::None {
+                colour_input(background.colour, Msg::ChangeBackgroundColour)
+            } else {
+                empty![]
+            },
+            if background.fill == Fill::Gradient {
+                colour_input(background.gradient_to, Msg::ChangeGradientColour)
+            } else {
+                empty![]
+            }
+        ],
+        label![
+            C!["flex items-center mt-2 w-full"],
+            "Frame",
+            input![
+                C!["form-checkbox ml-2"],
+                attrs! {
+                    At::Type => "checkbox",
+                    At::Checked => frame.shown.as_at_value()
+                },
+                ev(Ev::Click, |_| Msg::ToggleFrame)
+            ],
+            IF!(frame.shown => div![C!["ml-2"], colour_input(frame.colour, Msg::ChangeFrameColour)])
+        ],
+        if frame.shown {
+            div![
+                C!["grid grid-cols-2 gap-2 w-full mt-2 text-sm items-center"],
+                label!["Width"],
+                decoration_input(frame.width, Msg::ChangeFrameWidth),
+                label!["Inset"],
+                decoration_input(frame.inset, Msg::ChangeFrameInset),
+                label!["Corners"],
+                select![
+                    C!["form-select py-1"],
+                    Corners::ALL
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, corners)| option![
+                            attrs! {
+                                At::Value => idx,
+                                At::Selected => (*corners == frame.corners).as_at_value()
+                            },
+                            corners.name()
+                        ]),
+                    input_ev(Ev::Change, |value| {
+                        value
+                            .parse::<usize>()
+                            .ok()
+                            .and_then(|idx| Corners::ALL.get(idx))
+                            .map(|corners| Msg::ChangeFrameCorners(*corners))
+                    })
+                ],
+                if frame.corners != Corners::Square {
+                    vec![
+                        label!["Corner size"],
+                        decoration_input(frame.corner_size, Msg::ChangeCornerSize),
+                    ]
+                } else {
+                    vec![]
+                }
+            ]
+        } else {
+            empty![]
+        }
+    ]
+}
+
+fn markers_view(model: &Model) -> Node<Msg> {
+    let markers = &model.drawing.markers;
+    div![
+        C!["p-2 w-full flex flex-col my-2"],
+        label![C!["text-left mb-1 w-full"], "Markers:"],
+        marker_view("Unused points", MarkerKind::Unused, markers.unused),
+        marker_view("Line ends", MarkerKind::Endpoint, markers.endpoint),
+        marker_view("Stations (3+ lines)", MarkerKind::Station, markers.station),
+    ]
+}
+
+fn marker_view(text: &str, kind: MarkerKind, marker: Marker) -> Node<Msg> {
+    let filled = matches!(marker.shape, MarkerShape::Circle | MarkerShape::Square);
+    let change = move |change: fn(&mut Marker, String)| {
+        move |value: String| {
+            let mut marker = marker;
+            change(&mut marker, value);
+            Msg::ChangeMarker(kind, marker)
+        }
+    };
+    div![
+        C!["grid grid-cols-2 gap-1 w-full text-sm items-center mb-2"],
+        span![C!["col-span-2 font-semibold"], text],
+        select![
+            C!["form-select py-1"],
+            MarkerShape::ALL
+                .iter()
+                .enumerate()
+                .map(|(idx, shape)| option![
+                    attrs! {
+                        At::Value => idx,
+                        At::Selected => (*shape == marker.shape).as_at_value()
+                    },
+                    shape.name()
+                ]),
+            input_ev(
+                Ev::Change,
+                change(|marker, value| {
+                    if let Some(shape) = value
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|idx| MarkerShape::ALL.get(idx))
+                    {
+                        marker.shape = *shape;
+                    }
+                })
+            )
+        ],
+        input![
+            C!["form-input py-1"],
+            attrs! {
+                At::Type => "number",
+                At::Title => "Size",
+                At::Min => 0,
+                At::Step => 2,
+                At::Value => marker.size
+            },
+            input_ev(
+                Ev::Change,
+                change(|marker, value| marker.size = value.parse().unwrap_or(marker.size))
+            )
+        ],
+        IF!(filled => marker_colour_view("Own fill", marker.fill, move |fill| {
+            Msg::ChangeMarker(kind, Marker { fill, ..marker })
+        })),
+        IF!(marker.shape != MarkerShape::None => {
+            marker_colour_view("Own stroke", marker.stroke, move |stroke| {
+                Msg::ChangeMarker(kind, Marker { stroke, ..marker })
+            })
+        }),
+        // Filled shapes only have a stroke when it's given its own colour
+        if (filled && marker.stroke.is_some())
+            || matches!(marker.shape, MarkerShape::Ring | MarkerShape::Cross)
+        {
+            vec![
+                span!["Stroke width"],
+                input![
+                    C!["form-input py-1"],
+                    attrs! {
+                        At::Type => "number",
+                        At::Min => 0,
+                        At::Step => 1,
+                        At::Value => marker.stroke_width
+                    },
+                    input_ev(
+                        Ev::Change,
+                        change(|marker, value| {
+                            marker.stroke_width = value.parse().unwrap_or(marker.stroke_width)
+                        })
+                    )
+                ],
+            ]
+        } else {
+            vec![]
+        }
+    ]
+}
+
+/// A checkbox giving a marker a colour of its own rather than following the lines, & a picker
+/// for the colour once it has one
+fn marker_colour_view(
+    text: &str,
+    colour: Option<Colour>,
+    on_change: impl FnOnce(Option<Colour>) -> Msg + Clone + 'static,
+) -> Vec<Node<Msg>> {
+    let toggle = on_change.clone();
+    vec![
+        label![
+            C!["flex items-center"],
+            text,
+            input![
+                C!["form-checkbox ml-2"],
+                attrs! {
+                    At::Type => "checkbox",
+                    At::Checked => colour.is_some().as_at_value()
+                },
+                ev(Ev::Click, move |_| toggle(match colour {
+                    Some(_) => None,
+                    None => Some(Colour::BLACK),
+                }))
+            ]
+        ],
+        match colour {
+            Some(colour) => colour_input(colour, move |colour| on_change(Some(colour))),
+            None => span![],
+        },
+    ]
+}
+
+fn colour_input(
+    colour: Colour,
+    on_change: impl FnOnce(Colour) -> Msg + Clone + 'static,
+) -> Node<Msg> {
+    input![
+        C!["w-8 flex-shrink-0"],
+        attrs! {At::Type => "color", At::Value => colour.to_hex()},
+        input_ev(Ev::Input, move |value| Colour::from_hex(&value)
+            .map(on_change))
+    ]
+}
+
+fn decoration_input(value: f64, on_change: impl FnOnce(f64) -> Msg + Clone + 'static) -> Node<Msg> {
+    input![
+        C!["form-input py-1"],
+        attrs! {
+            At::Type => "number",
+            At::Min => 0,
+            At::Step => 5,
+            At::Value => value
+        },
+        input_ev(Ev::Change, |value| value.parse().ok().map(on_change))
+    ]
+}
+
+fn symmetry_view(model: &Model) -> Node<Msg> {
+    div![
+        C!["p-2 w-full flex flex-col items-center my-2"],
+        label![C!["text-left mb-1 w-full"], "Symmetry:"],
+        select![
+            C!["form-select w-full"],
+            Symmetry::ALL
+                .iter()
+                .enumerate()
+                .map(|(idx, symmetry)| option![
+                    attrs! {
+                        At::Value => idx,
+                        At::Selected => (*symmetry == model.symmetry).as_at_value(),
+                        At::Disabled => (!symmetry.fits(&model.drawing)).as_at_value()
+                    },
+                    symmetry.name(),
+                    IF!(!symmetry.fits(&model.drawing) => " (square grids)")
+                ]),
+            input_ev(Ev::Change, |value| {
+                value
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|idx| Symmetry::ALL.get(idx))
+                    .map(|symmetry| Msg::ChangeSymmetry(*symmetry))
+            })
+        ],
+        // The grid can stop fitting after the symmetry is picked
+        IF!(!model.symmetry.fits(&model.drawing) => div![
+            C!["text-sm text-red-600 w-full"],
+            DrawingError::NotSquare.to_string()
+        ]),
+        label![C!["text-left mt-2 mb-1 w-full"], "Transform:"],
+        div![
+            C!["grid grid-cols-2 gap-2 w-full"],
+            Transform::ALL.iter().map(|transform| {
+                let transform = *transform;
+                button![
+                    C![
+                        "py-1 px-2 text-sm bg-blue-500 text-white rounded-md shadow-md",
+                        "hover:bg-blue-700 focus:outline-none"
+                    ],
+                    transform.name(),
+                    ev(Ev::Click, move |_| Msg::Transform(transform))
+                ]
+            })
+        ]
+    ]
+}
+
+fn grid_size_input(value: u16, on_change: impl FnOnce(u16) -> Msg + Clone + 'static) -> Node<Msg> {
+    input![
+        C!["form-input w-full"],
+        attrs! {
+            At::Type => "number",
+            At::Min => 1,
+            At::Max => MAX_GRID_SIZE,
+            At::Step => 1,
+            At::Value => value
+        },
+        input_ev(Ev::Change, |value| value.parse().ok().map(on_change))
+    ]
+}
+
+/// Faint dots at the sub-grid & custom points, as a guide to where lines can be drawn
+fn guide_view(model: &Model) -> Vec<Node<Msg>> {
+    let drawing = &model.drawing;
+    let centres = drawing
+        .subgrid_points()
+        .filter(|(x, y)| x % CELL_STEPS != 0 || y % CELL_STEPS != 0)
+        .chain(drawing.custom_points().iter().copied())
+        .map(|point| drawing.position(point))
+        .collect();
+    render::shape_nodes(&[Shape::Group {
+        id: Some("guide".to_string()),
+        colour: Colour {
+            r: 160,
+            g: 174,
+            b: 192,
+        },
+        shapes: vec![Shape::Dots {
+            centres,
+            radius: POINT_RADIUS / 2_f64,
+        }],
+    }])
+}
+
 fn svg_view(model: &Model) -> Node<Msg> {
+    let (width, height) = (model.drawing.view_width(), model.drawing.view_height());
     div![
         C!["w-3/4 flex flex-grow justify-center w-full"],
         style! {
@@ -242,42 +1234,33 @@ fn svg_view(model: &Model) -> Node<Msg> {
         },
         svg![
             C!["w-full h-full"],
-            el_ref(&model.svg_ref),
             attrs! {
-                At::ViewBox => format!("0 0 {} {}", model.drawing.view_width, model.drawing.view_height),
+                At::ViewBox => format!("0 0 {} {}", model.drawing.view_width(), model.drawing.view_height()),
                 At::PreserveAspectRatio => "xMidYMid meet",
             },
+            guide_view(model),
             model.drawing.draw(),
-            model.next_line.map(|line| draw_line(
-                line,
-                model.drawing.x_spacing(),
-                model.drawing.y_spacing()
-            ))
+            mouse_ev(Ev::Click, move |e| {
+                let svg = e.current_target()?.dyn_into::<web_sys::Element>().ok()?;
+                let rect = svg.get_bounding_client_rect();
+                // The view box is scaled to fit & centred, as set by `preserveAspectRatio`
+                let scale = (rect.width() / width).min(rect.height() / height);
+                Some(Msg::CanvasClick((
+                    (e.client_x() as f64 - rect.left() - (rect.width() - width * scale) / 2_f64)
+                        / scale,
+                    (e.client_y() as f64 - rect.top() - (rect.height() - height * scale) / 2_f64)
+                        / scale,
+                )))
+            }),
+            model.next_line.map(|line| {
+                model
+                    .symmetry
+                    .lines(&model.drawing, line)
+                    .unwrap_or_else(|_| vec![line])
+                    .into_iter()
+                    .flat_map(|line| model.drawing.draw_line(line))
+                    .collect::<Vec<_>>()
+            })
         ]
     ]
 }
-
-fn draw_line(
-    ((from_x, from_y), (to_x, to_y)): ((i16, i16), (i16, i16)),
-    x_spacing: f64,
-    y_spacing: f64,
-) -> Vec<Node<Msg>> {
-    let mut ret = Vec::new();
-
-    let from_x = x_spacing * (from_x + 1) as f64;
-    let from_y = y_spacing * (from_y + 1) as f64;
-    let to_x = x_spacing * (to_x + 1) as f64;
-    let to_y = y_spacing * (to_y + 1) as f64;
-
-    ret.push(line_![
-        attrs! {At::X1 => from_x, At::Y1 => from_y, At::X2 => to_x, At::Y2 => to_y, At::Stroke => "black", At::StrokeWidth => 20}
-    ]);
-    ret.push(circle![
-        attrs! {At::Cx => from_x, At::Cy => from_y, At::R => 10}
-    ]);
-    ret.push(circle![
-        attrs! {At::Cx => to_x, At::Cy => to_y, At::R => 10}
-    ]);
-
-    ret
-}
@@ -0,0 +1,354 @@
+use rand::Rng;
+use seed::Attrs;
+use seed::{prelude::*, *};
+
+use crate::storage::{self, BREED_KEY, EXPLORE_KEY};
+use crate::{icons, utils};
+use track_drawer::drawing::{Drawing, Subgrid, MAX_GRID_SIZE};
+use track_drawer::export::svg;
+use track_drawer::generate::breed::{self, Mutation};
+use track_drawer::generate::{self, Settings};
+
+const CANDIDATES: usize = 12;
+const THUMBNAIL_WIDTH: u32 = 480;
+
+struct Candidate {
+    drawing: Drawing,
+    /// Image data URL, rendered once when the candidate is generated
+    thumbnail: String,
+    starred: bool,
+    saved: bool,
+}
+
+impl Candidate {
+    fn new(drawing: Drawing) -> Candidate {
+        Candidate {
+            thumbnail: utils::svg_data_url(&svg::render(&drawing.scene(), THUMBNAIL_WIDTH, None)),
+            drawing,
+            starred: false,
+            saved: false,
+        }
+    }
+}
+
+pub struct Model {
+    settings: Settings,
+    candidates: Vec<Candidate>,
+    /// Seed for the next candidate, seeds being used one after another
+    next_seed: u64,
+    /// Drawings to breed candidates from rather than generating them, if any
+    parents: Vec<Drawing>,
+    /// How many times starred candidates have been bred from
+    generation: usize,
+}
+
+pub enum Msg {
+    ToggleStar(usize),
+    Regenerate,
+    SaveStarred,
+    ChangeGridWidth(u16),
+    ChangeRows(u16),
+    ChangeLinesPerRow(u16),
+    TogglePoints,
+    ChangeSubgrid(Subgrid),
+    NextGeneration,
+    StopBreeding,
+}
+
+pub fn init(_orders: &mut impl Orders<Msg>) -> Model {
+    let mut model = Model {
+        settings: LocalStorage::get(EXPLORE_KEY).unwrap_or_default(),
+        candidates: Vec::new(),
+        // Leave room to count up from the first seed without overflowing
+        next_seed: rand::thread_rng().gen_range(0..u32::MAX as u64),
+        parents: take_parents(),
+        generation: 1,
+    };
+    model.regenerate();
+    model
+}
+
+/// Takes the drawings the gallery left to breed from, if any
+fn take_parents() -> Vec<Drawing> {
+    let parents = SessionStorage::get(BREED_KEY).unwrap_or_default();
+    SessionStorage::remove(BREED_KEY).ok();
+    parents
+}
+
+impl Model {
+    fn candidate(&self, seed: u64) -> Candidate {
+        let drawing = breed::breed(&self.parents, &Mutation::default(), seed)
+            .unwrap_or_else(|| generate::generate(&self.settings, seed));
+        Candidate::new(drawing)
+    }
+
+    /// Replaces every candidate that isn't starred, filling up to a full grid
+    fn regenerate(&mut self) {
+        let mut kept: Vec<Candidate> = self.candidates.drain(..).filter(|c| c.starred).collect();
+        let mut candidates = Vec::with_capacity(CANDIDATES);
+        for _ in 0..CANDIDATES.saturating_sub(kept.len()) {
+            candidates.push(self.candidate(self.next_seed));
+            self.next_seed += 1;
+        }
+        // Starred candidates keep their place at the front
+        kept.append(&mut candidates);
+        self.candidates = kept;
+    }
+
+    fn change_settings(&mut self, change: impl FnOnce(&mut Settings)) {
+        change(&mut self.settings);
+        LocalStorage::insert(EXPLORE_KEY, &self.settings).expect("Saving explore settings failed");
+        self.regenerate();
+    }
+
+    fn unsaved_stars(&self) -> usize {
+        self.candidates
+            .iter()
+            .filter(|c| c.starred && !c.saved)
+            .count()
+    }
+}
+
+pub fn update(msg: Msg, model: &mut Model, _orders: &mut impl Orders<Msg>) {
+    match msg {
+        Msg::ToggleStar(idx) => {
+            if let Some(candidate) = model.candidates.get_mut(idx) {
+                candidate.starred = !candidate.starred;
+            }
+        }
+        Msg::Regenerate => model.regenerate(),
+        Msg::SaveStarred => {
+            let mut saved_drawings = storage::load_drawings();
+            for candidate in model.candidates.iter_mut() {
+                if candidate.starred && !candidate.saved {
+                    let mut drawing = candidate.drawing.clone();
+                    drawing.title = storage::default_title(&saved_drawings);
+                    drawing.created = Some(utils::now());
+                    drawing.modified = drawing.created.clone();
+                    saved_drawings.push(drawing);
+                    candidate.saved = true;
+                }
+            }
+            storage::save_drawings(&saved_drawings);
+        }
+        Msg::ChangeGridWidth(width) => model.change_settings(|settings| {
+            settings.grid_width = width.clamp(1, MAX_GRID_SIZE);
+        }),
+        Msg::ChangeRows(rows) => model.change_settings(|settings| {
+            settings.rows = rows.clamp(1, MAX_GRID_SIZE);
+        }),
+        Msg::ChangeLinesPerRow(lines) => model.change_settings(|settings| {
+            settings.lines_per_row = lines;
+        }),
+        Msg::TogglePoints => model.change_settings(|settings| {
+            settings.includes_points = !settings.includes_points;
+        }),
+        Msg::ChangeSubgrid(subgrid) => model.change_settings(|settings| {
+            settings.subgrid = subgrid;
+        }),
+        Msg::NextGeneration => {
+            let starred: Vec<Drawing> = model
+                .candidates
+                .iter()
+                .filter(|c| c.starred)
+                .map(|c| c.drawing.clone())
+                .collect();
+            if !starred.is_empty() {
+                model.parents = starred;
+                model.generation += 1;
+                model.candidates.clear();
+                model.regenerate();
+            }
+        }
+        Msg::StopBreeding => {
+            model.parents.clear();
+            model.generation = 1;
+            model.regenerate();
+        }
+    }
+}
+
+fn button_class(disabled: bool) -> Attrs {
+    C![
+        "py-2 px-4 bg-blue-500 text-white font-semibold rounded-md shadow-md",
+        "focus:outline-none focus:ring-4 focus:ring-indigo-400 focus:ring-opacity-75",
+        if disabled {
+            "opacity-50 cursor-default"
+        } else {
+            "hover:bg-blue-700"
+        }
+    ]
+}
+
+pub fn view(model: &Model) -> Node<Msg> {
+    let unsaved = model.unsaved_stars();
+    div![
+        C!["m-16 flex flex-col items-center"],
+        div![
+            C!["flex flex-row items-center space-x-4 mb-8"],
+            if model.parents.is_empty() {
+                settings_view(&model.settings)
+            } else {
+                breeding_view(model)
+            },
+            button![
+                button_class(false),
+                "Regenerate",
+                attrs! {At::Title => "Replace the drawings that aren't starred"},
+                ev(Ev::Click, |_| Msg::Regenerate)
+            ],
+            button![
+                button_class(unsaved == 0),
+                if unsaved == 0 {
+                    "Save starred".to_string()
+                } else {
+                    format!("Save {} starred", unsaved)
+                },
+                attrs! {At::Disabled => (unsaved == 0).as_at_value()},
+                ev(Ev::Click, |_| Msg::SaveStarred)
+            ]
+        ],
+        div![
+            C!["grid gap-8 grid-flow-row grid-cols-4"],
+            model
+                .candidates
+                .iter()
+                .enumerate()
+                .map(|(idx, candidate)| candidate_view(idx, candidate))
+        ]
+    ]
+}
+
+fn settings_view(settings: &Settings) -> Vec<Node<Msg>> {
+    vec![
+        settings_input("Width", settings.grid_width, Msg::ChangeGridWidth),
+        settings_input("Rows", settings.rows, Msg::ChangeRows),
+        settings_input(
+            "Lines per row",
+            settings.lines_per_row,
+            Msg::ChangeLinesPerRow,
+        ),
+        label![
+            C!["flex items-center"],
+            "Points",
+            input![
+                C!["form-checkbox ml-2"],
+                attrs! {
+                    At::Type => "checkbox",
+                    At::Checked => settings.includes_points.as_at_value()
+                },
+                ev(Ev::Click, |_| Msg::TogglePoints)
+            ]
+        ],
+        select![
+            C!["form-select"],
+            attrs! {At::Title => "Points lines can be drawn to"},
+            Subgrid::ALL
+                .iter()
+                .enumerate()
+                .map(|(idx, subgrid)| option![
+                    attrs! {
+                        At::Value => idx,
+                        At::Selected => (*subgrid == settings.subgrid).as_at_value()
+                    },
+                    subgrid.name()
+                ]),
+            input_ev(Ev::Change, |value| {
+                value
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|idx| Subgrid::ALL.get(idx))
+                    .map(|subgrid| Msg::ChangeSubgrid(*subgrid))
+            })
+        ],
+    ]
+}
+
+fn breeding_view(model: &Model) -> Vec<Node<Msg>> {
+    let no_stars = !model.candidates.iter().any(|c| c.starred);
+    vec![
+        span![format!(
+            "Generation {} from {} {}",
+            model.generation,
+            model.parents.len(),
+            if model.parents.len() == 1 {
+                "parent"
+            } else {
+                "parents"
+            }
+        )],
+        button![
+            button_class(no_stars),
+            "Next generation",
+            attrs! {
+                At::Title => "Breed from the starred drawings",
+                At::Disabled => no_stars.as_at_value()
+            },
+            ev(Ev::Click, |_| Msg::NextGeneration)
+        ],
+        button![
+            button_class(false),
+            "Stop breeding",
+            attrs! {At::Title => "Go back to generating drawings from scratch"},
+            ev(Ev::Click, |_| Msg::StopBreeding)
+        ],
+    ]
+}
+
+fn settings_input(
+    text: &str,
+    value: u16,
+    on_change: impl FnOnce(u16) -> Msg + Clone + 'static,
+) -> Node<Msg> {
+    label![
+        C!["flex items-center"],
+        text,
+        input![
+            C!["form-input w-20 ml-2"],
+            attrs! {
+                At::Type => "number",
+                At::Min => 1,
+                At::Max => MAX_GRID_SIZE,
+                At::Value => value
+            },
+            input_ev(Ev::Change, |value| value.parse().ok().map(on_change))
+        ]
+    ]
+}
+
+fn candidate_view(idx: usize, candidate: &Candidate) -> Node<Msg> {
+    div![
+        C![
+            "rounded shadow-lg relative border border-blue-500 border-opacity-25 cursor-pointer",
+            IF!(candidate.starred => "ring-4")
+        ],
+        img![
+            C!["h-64 w-full object-contain"],
+            attrs! {At::Src => candidate.thumbnail}
+        ],
+        div![
+            C!["flex flex-row justify-between items-center px-3 py-2 text-sm text-gray-600"],
+            span![format!(
+                "Seed {}",
+                candidate.drawing.seed.unwrap_or_default()
+            )],
+            span![
+                C![
+                    "w-6",
+                    if candidate.starred {
+                        "text-yellow-500 fill-current"
+                    } else {
+                        "text-gray-400"
+                    }
+                ],
+                icons::star()
+            ]
+        ],
+        IF!(candidate.saved => div![
+            C!["absolute top-0 right-0 m-2 px-2 rounded bg-green-100 text-green-800 text-xs"],
+            "Saved"
+        ]),
+        attrs! {At::Title => "Click to star"},
+        ev(Ev::Click, move |_| Msg::ToggleStar(idx))
+    ]
+}
@@ -1,55 +1,320 @@
-use crate::drawing::Drawing;
-use crate::storage::STORAGE_KEY;
-use crate::{icons, utils};
+use std::collections::{HashMap, HashSet};
+
+use crate::storage::{BREED_KEY, REMIX_KEY};
+use crate::{export_dialog, icons, stats_panel, storage, utils};
+use rand::Rng;
+use seed::Attrs;
 use seed::{prelude::*, *};
+use track_drawer::drawing::Drawing;
+use track_drawer::export::{pdf, svg};
+use track_drawer::generate::{self, Keep};
+
+mod query;
+
+use query::{Query, Sort};
+
+/// Choices for how many drawings to print per page, as columns & rows
+const GALLERY_LAYOUTS: [(u16, u16); 5] = [(1, 1), (1, 2), (2, 2), (2, 3), (3, 3)];
+/// Cards shown per page of the gallery, a multiple of its three columns
+const PAGE_SIZE: usize = 24;
+const THUMBNAIL_WIDTH: u32 = 480;
 
 pub struct Model {
-    drawings: Vec<(Drawing, ElRef<web_sys::HtmlElement>)>,
-    selected: Option<usize>,
+    drawings: Vec<Drawing>,
+    query: Query,
+    /// Indices of the drawings matching the query, in the order shown
+    visible: Vec<usize>,
+    page: usize,
+    /// Thumbnails as data URLs, keyed by `Drawing::content_hash`
+    thumbnails: HashMap<u64, String>,
+    /// Indices of the selected drawings
+    selected: Vec<usize>,
+    /// The drawing a shift-click selects a range from
+    anchor: Option<usize>,
+
+    export: export_dialog::Model,
+    /// The drawings the export dialog was opened for
+    exporting: Vec<usize>,
+
+    gallery_options: pdf::Options,
+    gallery_captions: bool,
+
+    remix_keep: Keep,
 }
 
 #[derive(Debug)]
 pub enum Msg {
     Download(usize),
     Delete(usize),
+    Duplicate(usize),
+    Remix(usize),
+    ChangeRemixKeep(Keep),
     Select(usize),
+    ToggleSelected(usize),
+    SelectRange(usize),
+    SelectAll,
     UnSelect,
+    ExportSelected,
+    BreedSelected,
     TogglePoints(usize),
+    ChangeTitle(usize, String),
+    ChangeDescription(usize, String),
+    ChangeTags(usize, String),
+    PrintGallery,
+    ChangeGalleryPaper(pdf::PaperSize),
+    ChangeGalleryLayout((u16, u16)),
+    ToggleGalleryCaptions,
+    Export(export_dialog::Msg),
+    ChangeSearch(String),
+    ChangeWidthFilter(Option<u16>),
+    ChangeRowsFilter(Option<u16>),
+    ChangeMinLines(Option<usize>),
+    ChangeMaxLines(Option<usize>),
+    ChangePointsFilter(Option<bool>),
+    ChangeSort(Sort),
+    ClearFilters,
+    ChangePage(usize),
+}
+
+pub fn init(url: &Url, _orders: &mut impl Orders<Msg>) -> Model {
+    let mut model = Model {
+        drawings: storage::load_drawings(),
+        query: Query::from_url(url),
+        visible: Vec::new(),
+        page: 0,
+        thumbnails: HashMap::new(),
+        selected: Vec::new(),
+        anchor: None,
+        export: export_dialog::Model::default(),
+        exporting: Vec::new(),
+        gallery_options: pdf::Options {
+            columns: 2,
+            rows: 2,
+            ..pdf::Options::default()
+        },
+        gallery_captions: true,
+        remix_keep: Keep::default(),
+    };
+    model.refresh();
+    model
 }
 
-pub fn init(_orders: &mut impl Orders<Msg>) -> Model {
-    let mut drawings: Vec<Drawing> = LocalStorage::get(STORAGE_KEY).unwrap_or_default();
+impl Model {
+    /// Changes a saved drawing, marking it as modified
+    fn edit(&mut self, idx: usize, change: impl FnOnce(&mut Drawing)) {
+        if let Some(drawing) = self.drawings.get_mut(idx) {
+            change(drawing);
+            drawing.modified = Some(utils::now());
+            self.save();
+        }
+    }
+
+    /// Saves the drawings after they change, dropping thumbnails of drawings no longer saved
+    fn save(&mut self) {
+        storage::save_drawings(&self.drawings);
+        let hashes: HashSet<u64> = self.drawings.iter().map(Drawing::content_hash).collect();
+        self.thumbnails.retain(|hash, _| hashes.contains(hash));
+        self.refresh();
+    }
+
+    /// Re-runs the query after the drawings or query change, & renders thumbnails for the
+    /// current page that aren't cached yet
+    fn refresh(&mut self) {
+        self.visible = self.query.apply(&self.drawings);
+        self.page = self.page.min(self.page_count() - 1);
+        for idx in self
+            .visible
+            .iter()
+            .skip(self.page * PAGE_SIZE)
+            .take(PAGE_SIZE)
+        {
+            let drawing = &self.drawings[*idx];
+            self.thumbnails
+                .entry(drawing.content_hash())
+                .or_insert_with(|| {
+                    utils::svg_data_url(&svg::render(&drawing.scene(), THUMBNAIL_WIDTH, None))
+                });
+        }
+    }
+
+    fn page_count(&self) -> usize {
+        self.visible.len().div_ceil(PAGE_SIZE).max(1)
+    }
+
+    /// Indices of the drawings on the current page
+    fn page_items(&self) -> &[usize] {
+        let start = (self.page * PAGE_SIZE).min(self.visible.len());
+        &self.visible[start..(start + PAGE_SIZE).min(self.visible.len())]
+    }
+
+    /// Changes the query, keeping the URL in step so it survives a reload
+    fn change_query(&mut self, change: impl FnOnce(&mut Query)) {
+        change(&mut self.query);
+        self.page = 0;
+        self.refresh();
+        Url::current()
+            .set_search(self.query.to_search())
+            .go_and_replace();
+    }
 
-    Model {
-        drawings: drawings.drain(..).map(move |d| (d, ElRef::new())).collect(),
-        selected: None,
+    /// The selected drawings that match the query, in the order shown
+    fn visible_selection(&self) -> Vec<usize> {
+        self.visible
+            .iter()
+            .copied()
+            .filter(|idx| self.selected.contains(idx))
+            .collect()
     }
 }
 
-pub fn update(msg: Msg, model: &mut Model, _orders: &mut impl Orders<Msg>) {
+fn pick<'a>(drawings: &'a [Drawing], indices: &[usize]) -> Vec<&'a Drawing> {
+    indices
+        .iter()
+        .filter_map(|idx| drawings.get(*idx))
+        .collect()
+}
+
+pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
     match msg {
         Msg::Download(idx) => {
-            if let Some((_, el_ref)) = model.drawings.get(idx) {
-                utils::download_svg(el_ref);
-            }
+            model.exporting = vec![idx];
+            orders.send_msg(Msg::Export(export_dialog::Msg::Open));
         }
         Msg::Delete(idx) => {
             model.drawings.remove(idx);
+            model.save();
+            model.selected.clear();
+            model.anchor = None;
+        }
+        Msg::Duplicate(idx) => {
+            if let Some(drawing) = model.drawings.get(idx) {
+                let mut copy = drawing.clone();
+                copy.title = format!("{} copy", drawing.title);
+                copy.created = Some(utils::now());
+                copy.modified = copy.created.clone();
+                model.drawings.insert(idx + 1, copy);
+                model.selected.clear();
+                model.anchor = None;
+                model.save();
+            }
+        }
+        Msg::Remix(idx) => {
+            if let Some(drawing) = model.drawings.get(idx) {
+                // Only fails with the active layer locked, when the remix button is disabled
+                let seed = rand::thread_rng().gen();
+                if let Ok(mut remixed) = generate::remix(drawing, model.remix_keep, seed) {
+                    remixed.title = format!("{} remix", drawing.title);
+                    SessionStorage::insert(REMIX_KEY, &remixed).expect("Opening remix failed");
+                    orders.request_url(Url::new().add_path_part(crate::DRAW));
+                }
+            }
+        }
+        Msg::ChangeRemixKeep(keep) => {
+            model.remix_keep = keep;
         }
         Msg::Select(idx) => {
-            model.selected = Some(idx);
+            model.selected = vec![idx];
+            model.anchor = Some(idx);
+        }
+        Msg::ToggleSelected(idx) => {
+            if let Some(pos) = model.selected.iter().position(|selected| *selected == idx) {
+                model.selected.remove(pos);
+            } else {
+                model.selected.push(idx);
+            }
+            model.anchor = Some(idx);
+        }
+        Msg::SelectRange(idx) => {
+            let position = |idx| model.visible.iter().position(|visible| *visible == idx);
+            if let Some(end) = position(idx) {
+                let start = model.anchor.and_then(position).unwrap_or(end);
+                model.selected = model.visible[start.min(end)..=start.max(end)].to_vec();
+            }
+        }
+        Msg::SelectAll => {
+            model.selected = model.visible.clone();
         }
         Msg::UnSelect => {
-            model.selected = None;
+            model.selected.clear();
+            model.anchor = None;
+        }
+        Msg::ExportSelected => {
+            model.exporting = model.visible_selection();
+            orders.send_msg(Msg::Export(export_dialog::Msg::Open));
+        }
+        Msg::BreedSelected => {
+            let parents = pick(&model.drawings, &model.visible_selection());
+            if !parents.is_empty() {
+                SessionStorage::insert(BREED_KEY, &parents).expect("Opening breed failed");
+                orders.request_url(Url::new().add_path_part(crate::EXPLORE));
+            }
         }
         Msg::TogglePoints(idx) => {
-            if let Some((drawing, _)) = model.drawings.get_mut(idx) {
-                drawing.includes_points = !drawing.includes_points;
-                let drawings: Vec<Drawing> =
-                    model.drawings.iter().map(|(d, _)| (*d).clone()).collect();
-                LocalStorage::insert(STORAGE_KEY, &drawings).unwrap();
+            model.edit(idx, |drawing| {
+                drawing.includes_points = !drawing.includes_points
+            });
+        }
+        Msg::ChangeTitle(idx, title) => {
+            model.edit(idx, |drawing| drawing.title = title);
+        }
+        Msg::ChangeDescription(idx, description) => {
+            model.edit(idx, |drawing| drawing.description = description);
+        }
+        Msg::ChangeTags(idx, tags) => {
+            model.edit(idx, |drawing| drawing.set_tags(&tags));
+        }
+        Msg::PrintGallery => {
+            let selection = model.visible_selection();
+            let indices = if selection.is_empty() {
+                &model.visible
+            } else {
+                &selection
+            };
+            let date = utils::today();
+            let drawings: Vec<_> = pick(&model.drawings, indices)
+                .into_iter()
+                .map(|drawing| {
+                    let caption =
+                        Some(pdf::caption(drawing, &date)).filter(|_| model.gallery_captions);
+                    (drawing.scene(), caption)
+                })
+                .collect();
+            let bytes = pdf::render(&drawings, &model.gallery_options);
+            utils::download_bytes(&bytes, "application/pdf", "Track Gallery.pdf");
+        }
+        Msg::ChangeGalleryPaper(paper) => {
+            model.gallery_options.paper = paper;
+        }
+        Msg::ChangeGalleryLayout((columns, rows)) => {
+            model.gallery_options.columns = columns;
+            model.gallery_options.rows = rows;
+        }
+        Msg::ToggleGalleryCaptions => {
+            model.gallery_captions = !model.gallery_captions;
+        }
+        Msg::Export(msg) => {
+            let drawings = pick(&model.drawings, &model.exporting);
+            if !drawings.is_empty() {
+                export_dialog::update(msg, &mut model.export, &drawings);
             }
         }
+        Msg::ChangeSearch(text) => model.change_query(|query| query.text = text),
+        Msg::ChangeWidthFilter(width) => model.change_query(|query| query.grid_width = width),
+        Msg::ChangeRowsFilter(rows) => model.change_query(|query| query.rows = rows),
+        Msg::ChangeMinLines(min) => model.change_query(|query| query.min_lines = min),
+        Msg::ChangeMaxLines(max) => model.change_query(|query| query.max_lines = max),
+        Msg::ChangePointsFilter(points) => model.change_query(|query| query.has_points = points),
+        Msg::ChangeSort(sort) => model.change_query(|query| query.sort = sort),
+        Msg::ChangePage(page) => {
+            model.page = page;
+            model.refresh();
+        }
+        Msg::ClearFilters => model.change_query(|query| {
+            *query = Query {
+                sort: query.sort,
+                ..Query::default()
+            }
+        }),
     }
 }
 
@@ -58,6 +323,8 @@ pub fn view(model: &Model) -> Node<Msg> {
         C!["h-full flex flex-row"],
         sidebar_view(model),
         drawing_view(model),
+        export_dialog::view(&model.export, &pick(&model.drawings, &model.exporting))
+            .map(|node| node.map_msg(Msg::Export)),
         ev(Ev::Click, move |e| {
             e.stop_propagation();
             Msg::UnSelect
@@ -67,77 +334,410 @@ pub fn view(model: &Model) -> Node<Msg> {
 
 pub fn drawing_view(model: &Model) -> Node<Msg> {
     div![
-        C!["m-16 justify-center flex w-full"],
+        C!["m-16 justify-center flex flex-col items-center w-full"],
+        print_view(model),
+        search_view(model),
         div![
             C!["grid gap-8 grid-flow-row grid-cols-3"],
-            model
-                .drawings
+            model.page_items().iter().map(|idx| render_drawing(
+                *idx,
+                &model.drawings[*idx],
+                model.thumbnails.get(&model.drawings[*idx].content_hash()),
+                model.selected.contains(idx)
+            ))
+        ],
+        pagination_view(model)
+    ]
+}
+
+fn toolbar_button_class(disabled: bool) -> Attrs {
+    C![
+        "py-2 px-4 bg-blue-500 text-white font-semibold rounded-md shadow-md",
+        "focus:outline-none focus:ring-4 focus:ring-indigo-400 focus:ring-opacity-75",
+        if disabled {
+            "opacity-50 cursor-default"
+        } else {
+            "hover:bg-blue-700"
+        }
+    ]
+}
+
+fn print_view(model: &Model) -> Node<Msg> {
+    let options = &model.gallery_options;
+    let selection = model.visible_selection().len();
+    let no_selection = selection == 0;
+    div![
+        C!["flex flex-row items-center space-x-4 mb-8"],
+        // Keep the selection while using the toolbar
+        ev(Ev::Click, |e| e.stop_propagation()),
+        button![
+            toolbar_button_class(model.drawings.is_empty()),
+            "Select all",
+            attrs! {At::Disabled => model.drawings.is_empty().as_at_value()},
+            ev(Ev::Click, |_| Msg::SelectAll)
+        ],
+        button![
+            toolbar_button_class(no_selection),
+            if no_selection {
+                "Export selected".to_string()
+            } else {
+                format!("Export {} selected", selection)
+            },
+            attrs! {At::Disabled => no_selection.as_at_value()},
+            ev(Ev::Click, |_| Msg::ExportSelected)
+        ],
+        button![
+            toolbar_button_class(no_selection),
+            if no_selection {
+                "Breed selected".to_string()
+            } else {
+                format!("Breed {} selected", selection)
+            },
+            attrs! {
+                At::Title => "Cross & mutate the selected drawings into new ones to pick from",
+                At::Disabled => no_selection.as_at_value()
+            },
+            ev(Ev::Click, |_| Msg::BreedSelected)
+        ],
+        select![
+            C!["form-select"],
+            pdf::PaperSize::ALL.iter().enumerate().map(|(idx, paper)| option![
+                attrs! {
+                    At::Value => idx,
+                    At::Selected => (*paper == options.paper).as_at_value()
+                },
+                paper.name()
+            ]),
+            input_ev(Ev::Change, |value| {
+                value
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|idx| pdf::PaperSize::ALL.get(idx))
+                    .map(|paper| Msg::ChangeGalleryPaper(*paper))
+            })
+        ],
+        select![
+            C!["form-select"],
+            GALLERY_LAYOUTS
                 .iter()
                 .enumerate()
-                .map(|(idx, v)| render_drawing(idx, v, model.selected))
+                .map(|(idx, (columns, rows))| option![
+                    attrs! {
+                        At::Value => idx,
+                        At::Selected => (*columns == options.columns && *rows == options.rows).as_at_value()
+                    },
+                    format!("{} per page", columns * rows)
+                ]),
+            input_ev(Ev::Change, |value| {
+                value
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|idx| GALLERY_LAYOUTS.get(idx))
+                    .map(|layout| Msg::ChangeGalleryLayout(*layout))
+            })
+        ],
+        label![
+            C!["flex items-center"],
+            "Captions",
+            input![
+                C!["form-checkbox ml-2"],
+                attrs! {
+                    At::Type => "checkbox",
+                    At::Checked => model.gallery_captions.as_at_value()
+                },
+                ev(Ev::Click, |_| Msg::ToggleGalleryCaptions)
+            ]
+        ],
+        button![
+            toolbar_button_class(model.drawings.is_empty()),
+            if no_selection {
+                "Print gallery"
+            } else {
+                "Print selected"
+            },
+            attrs! {At::Disabled => model.drawings.is_empty().as_at_value()},
+            ev(Ev::Click, |_| Msg::PrintGallery)
         ]
     ]
 }
 
+fn search_view(model: &Model) -> Node<Msg> {
+    let query = &model.query;
+    div![
+        C!["flex flex-row flex-wrap items-center space-x-4 mb-8"],
+        ev(Ev::Click, |e| e.stop_propagation()),
+        input![
+            C!["form-input"],
+            attrs! {
+                At::Type => "search",
+                At::Placeholder => "Search names & tags",
+                At::Value => query.text
+            },
+            input_ev(Ev::Input, Msg::ChangeSearch)
+        ],
+        label![
+            C!["flex items-center"],
+            "Width",
+            filter_input(query.grid_width, Msg::ChangeWidthFilter)
+        ],
+        label![
+            C!["flex items-center"],
+            "Rows",
+            filter_input(query.rows, Msg::ChangeRowsFilter)
+        ],
+        label![
+            C!["flex items-center"],
+            "Lines",
+            filter_input(query.min_lines, Msg::ChangeMinLines),
+            span![C!["ml-2"], "to"],
+            filter_input(query.max_lines, Msg::ChangeMaxLines)
+        ],
+        select![
+            C!["form-select"],
+            [
+                (None, "Any points"),
+                (Some(true), "With points"),
+                (Some(false), "Without points")
+            ]
+            .iter()
+            .enumerate()
+            .map(|(idx, (points, name))| option![
+                attrs! {
+                    At::Value => idx,
+                    At::Selected => (*points == query.has_points).as_at_value()
+                },
+                name
+            ]),
+            input_ev(Ev::Change, |value| {
+                Msg::ChangePointsFilter(match value.as_str() {
+                    "1" => Some(true),
+                    "2" => Some(false),
+                    _ => None,
+                })
+            })
+        ],
+        select![
+            C!["form-select"],
+            Sort::ALL.iter().enumerate().map(|(idx, sort)| option![
+                attrs! {
+                    At::Value => idx,
+                    At::Selected => (*sort == query.sort).as_at_value()
+                },
+                sort.name()
+            ]),
+            input_ev(Ev::Change, |value| {
+                value
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|idx| Sort::ALL.get(idx))
+                    .map(|sort| Msg::ChangeSort(*sort))
+            })
+        ],
+        span![
+            C!["text-gray-600"],
+            format!("{} of {}", model.visible.len(), model.drawings.len())
+        ],
+        IF!(query.is_filtered() => button![
+            C!["underline focus:outline-none"],
+            "Clear filters",
+            ev(Ev::Click, |_| Msg::ClearFilters)
+        ])
+    ]
+}
+
+/// A number input for a filter, left empty to not filter on it
+fn filter_input<T: ToString + std::str::FromStr + 'static>(
+    value: Option<T>,
+    on_change: impl FnOnce(Option<T>) -> Msg + Clone + 'static,
+) -> Node<Msg> {
+    input![
+        C!["form-input w-20 ml-2"],
+        attrs! {
+            At::Type => "number",
+            At::Min => 0,
+            At::Value => value.map(|value| value.to_string()).unwrap_or_default()
+        },
+        input_ev(Ev::Change, |value| on_change(value.parse().ok()))
+    ]
+}
+
+fn pagination_view(model: &Model) -> Option<Node<Msg>> {
+    let pages = model.page_count();
+    if pages <= 1 {
+        return None;
+    }
+    let page = model.page;
+    Some(div![
+        C!["flex flex-row items-center space-x-4 mt-8"],
+        ev(Ev::Click, |e| e.stop_propagation()),
+        button![
+            toolbar_button_class(page == 0),
+            "Previous",
+            attrs! {At::Disabled => (page == 0).as_at_value()},
+            ev(Ev::Click, move |_| Msg::ChangePage(page.saturating_sub(1)))
+        ],
+        span![format!("Page {} of {}", page + 1, pages)],
+        button![
+            toolbar_button_class(page + 1 >= pages),
+            "Next",
+            attrs! {At::Disabled => (page + 1 >= pages).as_at_value()},
+            ev(Ev::Click, move |_| Msg::ChangePage(page + 1))
+        ]
+    ])
+}
+
 fn sidebar_view(model: &Model) -> Option<Node<Msg>> {
-    if let Some(idx) = model.selected {
-        if let Some((drawing, _ref)) = model.drawings.get(idx) {
-            Some(div![
-                C!["w-1/5 h-full bg-gray-100 flex flex-col flex-grow-0 overflow-auto"],
-                div![
-                    C!["flex justify-center p-2 m-2"],
-                    label![
-                        C!["flex items-center"],
-                        "Show points",
-                        input![
-                            C!["form-checkbox ml-2"],
-                            attrs! {
-                            At::Id => "show-points",
-                            At::Type => "checkbox",
-                            At::Checked => drawing.includes_points.as_at_value()
-                            },
-                            ev(Ev::Click, move |e| {
-                                e.stop_propagation();
-                                Msg::TogglePoints(idx)
-                            })
-                        ]
-                    ]
-                ],
-            ])
+    let idx = match model.selected[..] {
+        [idx] => idx,
+        _ => return None,
+    };
+    let drawing = model.drawings.get(idx)?;
+    Some(div![
+        C!["w-1/5 h-full bg-gray-100 flex flex-col flex-grow-0 overflow-auto p-4"],
+        // Keep the drawing selected while editing it
+        ev(Ev::Click, |e| e.stop_propagation()),
+        label![C!["mb-1"], "Title:"],
+        input![
+            C!["form-input w-full"],
+            attrs! {At::Type => "text", At::Value => drawing.title},
+            input_ev(Ev::Change, move |title| Msg::ChangeTitle(idx, title))
+        ],
+        label![C!["mt-2 mb-1"], "Description:"],
+        textarea![
+            C!["form-textarea w-full"],
+            attrs! {At::Rows => 4, At::Value => drawing.description},
+            input_ev(Ev::Change, move |description| Msg::ChangeDescription(
+                idx,
+                description
+            ))
+        ],
+        label![C!["mt-2 mb-1"], "Tags:"],
+        input![
+            C!["form-input w-full"],
+            attrs! {
+                At::Type => "text",
+                At::Placeholder => "Comma separated",
+                At::Value => drawing.tags().join(", ")
+            },
+            input_ev(Ev::Change, move |tags| Msg::ChangeTags(idx, tags))
+        ],
+        label![
+            C!["flex items-center mt-4"],
+            "Show points",
+            input![
+                C!["form-checkbox ml-2"],
+                attrs! {
+                At::Id => "show-points",
+                At::Type => "checkbox",
+                At::Checked => drawing.includes_points.as_at_value()
+                },
+                ev(Ev::Click, move |_| Msg::TogglePoints(idx))
+            ]
+        ],
+        remix_view(model, idx),
+        dl![
+            C!["grid grid-cols-2 gap-1 text-sm mt-4 text-gray-600"],
+            dt!["Created"],
+            dd![timestamp(&drawing.created)],
+            dt!["Modified"],
+            dd![timestamp(&drawing.modified)],
+        ],
+        label![C!["mt-4 mb-1"], "Stats:"],
+        stats_panel::view(drawing)
+    ])
+}
+
+fn remix_view(model: &Model, idx: usize) -> Node<Msg> {
+    let (count, rows) = match model.remix_keep {
+        Keep::Lines(count) => (count, false),
+        Keep::Rows(rows) => (rows as usize, true),
+    };
+    let keep = move |count: usize, rows: bool| {
+        if rows {
+            Keep::Rows(count.min(u16::MAX as usize) as u16)
         } else {
-            None
+            Keep::Lines(count)
         }
-    } else {
-        None
-    }
+    };
+    div![
+        C!["flex flex-col mt-4"],
+        label![C!["mb-1"], "Remix keeps the first:"],
+        div![
+            C!["flex flex-row"],
+            input![
+                C!["form-input w-20"],
+                attrs! {At::Type => "number", At::Min => 0, At::Value => count},
+                input_ev(Ev::Change, move |value| value
+                    .parse()
+                    .ok()
+                    .map(|count| Msg::ChangeRemixKeep(keep(count, rows))))
+            ],
+            select![
+                C!["form-select ml-2 flex-grow"],
+                option![
+                    attrs! {At::Value => "lines", At::Selected => (!rows).as_at_value()},
+                    "lines"
+                ],
+                option![
+                    attrs! {At::Value => "rows", At::Selected => rows.as_at_value()},
+                    "rows"
+                ],
+                input_ev(Ev::Change, move |value| Msg::ChangeRemixKeep(keep(
+                    count,
+                    value == "rows"
+                )))
+            ]
+        ],
+        button![
+            C!["mt-2"],
+            toolbar_button_class(false),
+            "Remix",
+            ev(Ev::Click, move |_| Msg::Remix(idx))
+        ]
+    ]
+}
+
+/// Shows an ISO 8601 timestamp to the minute
+fn timestamp(time: &Option<String>) -> String {
+    time.as_ref()
+        .map(|time| time.chars().take(16).collect::<String>().replace('T', " "))
+        .unwrap_or_else(|| "Unknown".to_string())
 }
 
 fn render_drawing(
     idx: usize,
-    (drawing, elref): &(Drawing, ElRef<web_sys::HtmlElement>),
-    selected: Option<usize>,
+    drawing: &Drawing,
+    thumbnail: Option<&String>,
+    is_selected: bool,
 ) -> Node<Msg> {
-    let is_selected = selected
-        .map(|selected_idx| selected_idx == idx)
-        .unwrap_or(false);
-
     let selected_attrs: Attrs = if is_selected {
         C!["border border-blue-500 border-opacity-25 ring-4"]
     } else {
         C!["border border-blue-500 border-opacity-25"]
     };
+    let locked = drawing.layers()[drawing.active_layer()].locked;
 
     div![
-        C!["rounded shadow-lg relative h-96"],
+        C!["rounded shadow-lg relative"],
         selected_attrs,
-        svg![
-            C!["h-96"],
-            el_ref(elref),
+        // A single image keeps the page light, however many lines & points the drawing has
+        img![
+            C!["h-96 w-full object-contain"],
             attrs! {
-                At::ViewBox => format!("0 0 {} {}", drawing.view_width, drawing.view_height),
-                At::PreserveAspectRatio => "xMidYMid meet",
-            },
-            drawing.draw(),
+                At::Src => thumbnail.cloned().unwrap_or_default(),
+                At::Alt => drawing.title
+            }
+        ],
+        div![
+            C!["px-3 py-2 pr-48 border-t border-blue-500 border-opacity-25"],
+            div![C!["font-semibold truncate"], &drawing.title],
+            div![
+                C!["flex flex-wrap text-xs"],
+                drawing.tags().iter().map(|tag| span![
+                    C!["bg-blue-100 text-blue-800 rounded px-2 mr-1 mt-1"],
+                    tag
+                ])
+            ]
         ],
         div![
             C!["absolute bottom-0 right-0"],
@@ -147,17 +747,46 @@ fn render_drawing(
                     "focus:outline-none"
                 ],
                 icons::download(),
+                attrs! {At::Title => "Download"},
                 ev(Ev::Click, move |_| Msg::Download(idx))
             ],
+            button![
+                C![
+                    "w-12 stroke-current text-blue-500 opacity-25 hover:opacity-100",
+                    "focus:outline-none"
+                ],
+                icons::duplicate(),
+                attrs! {At::Title => "Duplicate"},
+                ev(Ev::Click, move |_| Msg::Duplicate(idx))
+            ],
+            button![
+                C![
+                    "w-12 stroke-current text-blue-500 opacity-25 hover:opacity-100",
+                    "focus:outline-none"
+                ],
+                icons::remix(),
+                attrs! {
+                    At::Title => if locked { "Remix (the active layer is locked)" } else { "Remix" },
+                    At::Disabled => locked.as_at_value()
+                },
+                ev(Ev::Click, move |_| Msg::Remix(idx))
+            ],
             button![
                 C!["w-12 stroke-current text-red-500 opacity-25 hover:opacity-100 focus:outline-none"],
                 icons::remove(),
+                attrs! {At::Title => "Delete"},
                 ev(Ev::Click, move |_| Msg::Delete(idx))
             ]
         ],
-        ev(Ev::Click, move |e| {
+        mouse_ev(Ev::Click, move |e| {
             e.stop_propagation();
-            Msg::Select(idx)
+            if e.shift_key() {
+                Msg::SelectRange(idx)
+            } else if e.ctrl_key() || e.meta_key() {
+                Msg::ToggleSelected(idx)
+            } else {
+                Msg::Select(idx)
+            }
         })
     ]
 }
@@ -0,0 +1,286 @@
+use std::cmp::Reverse;
+
+use seed::prelude::*;
+use track_drawer::drawing::Drawing;
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Sort {
+    #[default]
+    Newest,
+    Oldest,
+    MostLines,
+    Name,
+}
+
+impl Sort {
+    pub const ALL: [Sort; 4] = [Sort::Newest, Sort::Oldest, Sort::MostLines, Sort::Name];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Sort::Newest => "Newest",
+            Sort::Oldest => "Oldest",
+            Sort::MostLines => "Most lines",
+            Sort::Name => "Name",
+        }
+    }
+
+    /// How the sort is written in the URL
+    fn key(&self) -> &'static str {
+        match self {
+            Sort::Newest => "newest",
+            Sort::Oldest => "oldest",
+            Sort::MostLines => "lines",
+            Sort::Name => "name",
+        }
+    }
+}
+
+/// Which saved drawings the gallery shows & in what order
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Query {
+    /// Text to find in titles & tags
+    pub text: String,
+    pub grid_width: Option<u16>,
+    pub rows: Option<u16>,
+    pub min_lines: Option<usize>,
+    pub max_lines: Option<usize>,
+    pub has_points: Option<bool>,
+    pub sort: Sort,
+}
+
+impl Query {
+    pub fn from_url(url: &Url) -> Query {
+        let search = url.search();
+        Query::from_params(|key| {
+            search
+                .get(key)
+                .and_then(|values| values.first())
+                .map(String::as_str)
+        })
+    }
+
+    /// Reads the query from URL search parameters, ignoring any values it doesn't recognise
+    fn from_params<'a>(value: impl Fn(&str) -> Option<&'a str>) -> Query {
+        Query {
+            text: value("q").map(str::to_string).unwrap_or_default(),
+            grid_width: value("width").and_then(|value| value.parse().ok()),
+            rows: value("rows").and_then(|value| value.parse().ok()),
+            min_lines: value("min_lines").and_then(|value| value.parse().ok()),
+            max_lines: value("max_lines").and_then(|value| value.parse().ok()),
+            has_points: value("points").and_then(|value| match value {
+                "yes" => Some(true),
+                "no" => Some(false),
+                _ => None,
+            }),
+            sort: value("sort")
+                .and_then(|key| Sort::ALL.iter().find(|sort| sort.key() == key))
+                .copied()
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn to_search(&self) -> UrlSearch {
+        UrlSearch::new(
+            self.params()
+                .into_iter()
+                .map(|(key, value)| (key, vec![value])),
+        )
+    }
+
+    /// The query as URL search parameters, leaving out anything not being filtered on
+    fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params: Vec<(&str, String)> = Vec::new();
+        if !self.text.is_empty() {
+            params.push(("q", self.text.clone()));
+        }
+        let numbers = [
+            ("width", self.grid_width.map(usize::from)),
+            ("rows", self.rows.map(usize::from)),
+            ("min_lines", self.min_lines),
+            ("max_lines", self.max_lines),
+        ];
+        for (key, number) in numbers {
+            if let Some(number) = number {
+                params.push((key, number.to_string()));
+            }
+        }
+        if let Some(has_points) = self.has_points {
+            params.push(("points", if has_points { "yes" } else { "no" }.to_string()));
+        }
+        if self.sort != Sort::default() {
+            params.push(("sort", self.sort.key().to_string()));
+        }
+        params
+    }
+
+    /// Whether anything other than the sort order is set
+    pub fn is_filtered(&self) -> bool {
+        *self
+            != Query {
+                sort: self.sort,
+                ..Query::default()
+            }
+    }
+
+    fn matches(&self, drawing: &Drawing) -> bool {
+        let text = self.text.trim().to_lowercase();
+        let lines = drawing.line_count();
+        (text.is_empty()
+            || drawing.title.to_lowercase().contains(&text)
+            || drawing
+                .tags()
+                .iter()
+                .any(|tag| tag.to_lowercase().contains(&text)))
+            && self
+                .grid_width
+                .is_none_or(|width| drawing.grid_width == width)
+            && self.rows.is_none_or(|rows| drawing.grid_height == rows)
+            && self.min_lines.is_none_or(|min| lines >= min)
+            && self.max_lines.is_none_or(|max| lines <= max)
+            && self
+                .has_points
+                .is_none_or(|points| drawing.includes_points == points)
+    }
+
+    /// Indices of the drawings matching the query, in its sort order
+    pub fn apply(&self, drawings: &[Drawing]) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..drawings.len())
+            .filter(|idx| self.matches(&drawings[*idx]))
+            .collect();
+        // Drawings are saved in the order they were made, so their position breaks any ties
+        match self.sort {
+            Sort::Newest => indices.sort_by_key(|idx| Reverse((&drawings[*idx].created, *idx))),
+            Sort::Oldest => indices.sort_by_key(|idx| (&drawings[*idx].created, *idx)),
+            Sort::MostLines => {
+                indices.sort_by_key(|idx| (Reverse(drawings[*idx].line_count()), *idx))
+            }
+            Sort::Name => indices.sort_by_key(|idx| (drawings[*idx].title.to_lowercase(), *idx)),
+        }
+        indices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_pairs(pairs: &[(&str, &str)]) -> Query {
+        Query::from_params(|key| {
+            pairs
+                .iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, value)| *value)
+        })
+    }
+
+    fn drawing(title: &str, created: &str, lines: usize) -> Drawing {
+        let mut drawing = Drawing::new();
+        drawing.title = title.to_string();
+        drawing.created = Some(created.to_string());
+        for idx in 0..lines {
+            drawing.add_line((0, 0), (60, idx as i16 % 2 * 60)).unwrap();
+        }
+        drawing
+    }
+
+    #[test]
+    fn round_trips_through_params() {
+        let query = Query {
+            text: "loop".to_string(),
+            grid_width: Some(8),
+            rows: Some(6),
+            min_lines: Some(2),
+            max_lines: Some(10),
+            has_points: Some(false),
+            sort: Sort::MostLines,
+        };
+        let params = query.params();
+        let pairs: Vec<(&str, &str)> = params
+            .iter()
+            .map(|(key, value)| (*key, value.as_str()))
+            .collect();
+        assert_eq!(from_pairs(&pairs), query);
+        for sort in Sort::ALL {
+            let query = Query {
+                sort,
+                ..Query::default()
+            };
+            assert_eq!(from_pairs(&[("sort", sort.key())]), query);
+        }
+    }
+
+    #[test]
+    fn leaves_out_defaults() {
+        assert!(Query::default().params().is_empty());
+        assert!(!Query::default().is_filtered());
+        let sorted = Query {
+            sort: Sort::Name,
+            ..Query::default()
+        };
+        assert_eq!(sorted.params(), vec![("sort", "name".to_string())]);
+        assert!(!sorted.is_filtered());
+    }
+
+    #[test]
+    fn falls_back_to_defaults_for_unrecognised_params() {
+        let query = from_pairs(&[
+            ("width", "wide"),
+            ("rows", "-1"),
+            ("min_lines", ""),
+            ("max_lines", "1.5"),
+            ("points", "maybe"),
+            ("sort", "random"),
+            ("colour", "red"),
+        ]);
+        assert_eq!(query, Query::default());
+    }
+
+    #[test]
+    fn filters_drawings() {
+        let mut tagged = drawing("Station", "2024-01-02", 3);
+        tagged.set_tags("Loop, yard");
+        let drawings = vec![
+            drawing("Big loop", "2024-01-01", 1),
+            tagged,
+            drawing("Branch", "2024-01-03", 5),
+        ];
+        let text = Query {
+            text: " LOOP ".to_string(),
+            ..Query::default()
+        };
+        assert_eq!(text.apply(&drawings), vec![1, 0]);
+        let lines = Query {
+            min_lines: Some(2),
+            max_lines: Some(4),
+            ..Query::default()
+        };
+        assert_eq!(lines.apply(&drawings), vec![1]);
+        let width = Query {
+            grid_width: Some(drawings[0].grid_width + 1),
+            ..Query::default()
+        };
+        assert!(width.apply(&drawings).is_empty());
+    }
+
+    #[test]
+    fn sorts_drawings() {
+        let drawings = vec![
+            drawing("beta", "2024-01-02", 1),
+            drawing("Alpha", "2024-01-03", 3),
+            drawing("gamma", "2024-01-01", 3),
+            drawing("alpha", "2024-01-03", 0),
+        ];
+        let sorted = |sort| {
+            Query {
+                sort,
+                ..Query::default()
+            }
+            .apply(&drawings)
+        };
+        // Ties keep the order the drawings were saved in, newest first when sorting by date
+        assert_eq!(sorted(Sort::Newest), vec![3, 1, 0, 2]);
+        assert_eq!(sorted(Sort::Oldest), vec![2, 0, 1, 3]);
+        assert_eq!(sorted(Sort::MostLines), vec![1, 2, 0, 3]);
+        assert_eq!(sorted(Sort::Name), vec![1, 3, 0, 2]);
+    }
+}
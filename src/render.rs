@@ -0,0 +1,197 @@
+use seed::{prelude::*, *};
+use serde::{Deserialize, Serialize};
+
+/// An RGB colour, as picked from an html colour input
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct Colour {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Colour {
+    pub const BLACK: Colour = Colour { r: 0, g: 0, b: 0 };
+    pub const WHITE: Colour = Colour {
+        r: 255,
+        g: 255,
+        b: 255,
+    };
+
+    /// Parses a colour of the form `#rrggbb`
+    pub fn from_hex(hex: &str) -> Option<Colour> {
+        let hex = hex.strip_prefix('#')?;
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |idx: usize| u8::from_str_radix(hex.get(idx..idx + 2)?, 16).ok();
+        Some(Colour {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+        })
+    }
+
+    pub fn to_hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+/// The basic shapes a drawing is made from, shared by the screen & every export format
+#[derive(Clone, Debug)]
+pub enum Shape {
+    /// A straight line with a stroke of the given width
+    Line {
+        from: (f64, f64),
+        to: (f64, f64),
+        width: f64,
+    },
+    /// A filled circle
+    Dot { centre: (f64, f64), radius: f64 },
+    /// Many filled circles of the same size, such as the grid points
+    Dots {
+        centres: Vec<(f64, f64)>,
+        radius: f64,
+    },
+    /// The whole canvas filled with a colour fading from top to bottom, the same colour for
+    /// a solid fill
+    Fill { top: Colour, bottom: Colour },
+    /// A closed outline with a stroke of the given width
+    Polygon { points: Vec<(f64, f64)>, width: f64 },
+    /// A filled outline
+    Area { points: Vec<(f64, f64)> },
+    /// An open line through each of the points with a stroke of the given width
+    Path { points: Vec<(f64, f64)>, width: f64 },
+    /// A filled square, `size` being half its width
+    Square { centre: (f64, f64), size: f64 },
+    /// An unfilled circle with a stroke of the given width
+    Ring {
+        centre: (f64, f64),
+        radius: f64,
+        width: f64,
+    },
+    /// Shapes drawn together in one colour, such as the lines of a layer
+    Group {
+        id: Option<String>,
+        colour: Colour,
+        shapes: Vec<Shape>,
+    },
+}
+
+/// Everything needed to draw a drawing, in the units of its view box
+#[derive(Clone, Debug)]
+pub struct Scene {
+    pub width: f64,
+    pub height: f64,
+    pub shapes: Vec<Shape>,
+}
+
+impl Scene {
+    pub fn nodes<Msg>(&self) -> Vec<Node<Msg>> {
+        shape_nodes(&self.shapes)
+    }
+}
+
+pub fn shape_nodes<Msg>(shapes: &[Shape]) -> Vec<Node<Msg>> {
+    shapes
+        .iter()
+        .map(|shape| shape_node(shape, Colour::BLACK))
+        .collect()
+}
+
+fn shape_node<Msg>(shape: &Shape, colour: Colour) -> Node<Msg> {
+    match shape {
+        Shape::Line {
+            from: (from_x, from_y),
+            to: (to_x, to_y),
+            width,
+        } => line_![
+            attrs! {At::X1 => from_x, At::Y1 => from_y, At::X2 => to_x, At::Y2 => to_y, At::Stroke => colour.to_hex(), At::StrokeWidth => width}
+        ],
+        Shape::Dot {
+            centre: (x, y),
+            radius,
+        } => circle![attrs! {At::Cx => x, At::Cy => y, At::R => radius}],
+        // A single path keeps the DOM small for large grids
+        Shape::Dots { centres, radius } => path![attrs! {At::D => dots_path(centres, *radius)}],
+        Shape::Fill { top, bottom } if top == bottom => rect![attrs! {
+            At::Width => "100%",
+            At::Height => "100%",
+            At::Fill => top.to_hex()
+        }],
+        Shape::Fill { top, bottom } => g![
+            defs![linearGradient![
+                attrs! {At::Id => "background", At::X1 => 0, At::Y1 => 0, At::X2 => 0, At::Y2 => 1},
+                stop![attrs! {At::Offset => 0, At::StopColor => top.to_hex()}],
+                stop![attrs! {At::Offset => 1, At::StopColor => bottom.to_hex()}]
+            ]],
+            rect![attrs! {
+                At::Width => "100%",
+                At::Height => "100%",
+                At::Fill => "url(#background)"
+            }]
+        ],
+        Shape::Polygon { points, width } => polygon![attrs! {
+            At::Points => points_list(points),
+            At::Fill => "none",
+            At::Stroke => colour.to_hex(),
+            At::StrokeWidth => width
+        }],
+        Shape::Area { points } => polygon![attrs! {At::Points => points_list(points)}],
+        Shape::Path { points, width } => polyline![attrs! {
+            At::Points => points_list(points),
+            At::Fill => "none",
+            At::Stroke => colour.to_hex(),
+            At::StrokeWidth => width
+        }],
+        Shape::Square {
+            centre: (x, y),
+            size,
+        } => rect![attrs! {
+            At::X => x - size,
+            At::Y => y - size,
+            At::Width => 2_f64 * size,
+            At::Height => 2_f64 * size
+        }],
+        Shape::Ring {
+            centre: (x, y),
+            radius,
+            width,
+        } => circle![attrs! {
+            At::Cx => x,
+            At::Cy => y,
+            At::R => radius,
+            At::Fill => "none",
+            At::Stroke => colour.to_hex(),
+            At::StrokeWidth => width
+        }],
+        Shape::Group { id, colour, shapes } => g![
+            id.as_ref().map(|id| attrs! {At::Id => id}),
+            attrs! {At::Fill => colour.to_hex()},
+            shapes.iter().map(|shape| shape_node(shape, *colour))
+        ],
+    }
+}
+
+/// Positions as the `points` of an SVG polygon or polyline
+pub fn points_list(points: &[(f64, f64)]) -> String {
+    points
+        .iter()
+        .map(|(x, y)| format!("{},{}", x, y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// SVG path data drawing a circle around each of `centres`
+pub fn dots_path(centres: &[(f64, f64)], radius: f64) -> String {
+    let mut path = String::new();
+    for (x, y) in centres {
+        path.push_str(&format!(
+            "M{} {}a{r} {r} 0 1 0 {d} 0a{r} {r} 0 1 0 -{d} 0",
+            x - radius,
+            y,
+            r = radius,
+            d = 2_f64 * radius
+        ));
+    }
+    path
+}
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+pub mod sketch;
+pub mod transit;
+
+/// How the lines of a drawing are laid out & styled
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Renderer {
+    /// Straight lines between grid points
+    #[default]
+    Plain,
+    /// Like a metro map, with lines routed at 45° angles & running side by side
+    Transit,
+    /// As if drawn by hand, with wobbly strokes that stay the same for the drawing's seed
+    Sketch,
+}
+
+impl Renderer {
+    pub const ALL: [Renderer; 3] = [Renderer::Plain, Renderer::Transit, Renderer::Sketch];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Renderer::Plain => "Plain",
+            Renderer::Transit => "Transit map",
+            Renderer::Sketch => "Sketch",
+        }
+    }
+
+    /// The renderer for plotting & cutting, which leaves out effects that aren't really there
+    pub fn for_machines(&self) -> Renderer {
+        match self {
+            Renderer::Sketch => Renderer::Plain,
+            renderer => *renderer,
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::f64::consts::{PI, TAU};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::drawing::Line;
+use crate::render::Shape;
+
+/// Strokes each line is drawn with
+const STROKES: usize = 2;
+/// Length of line between the points each stroke wobbles through
+const STEP: f64 = 25_f64;
+/// Furthest a stroke bows away from its line, as a share of the line's length
+const BOW: f64 = 0.015;
+/// Furthest a stroke wiggles either side of its bow, in line widths
+const WIGGLE: f64 = 0.2;
+/// Furthest a stroke overshoots or stops short of each end of its line, in line widths
+const OVERSHOOT: f64 = 0.5;
+/// Width of a stroke at its ends compared to its middle
+const TAPER: f64 = 0.4;
+
+/// Seed for the strokes of one line, so each line keeps its wobble as others are added
+pub(crate) fn line_seed(seed: u64, layer: usize, ((x1, y1), (x2, y2)): Line) -> u64 {
+    [layer as u64, x1 as u64, y1 as u64, x2 as u64, y2 as u64]
+        .iter()
+        // FNV-1a, as it's stable & the values are small
+        .fold(seed ^ 0xcbf2_9ce4_8422_2325, |hash, value| {
+            (hash ^ value).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+/// Filled outlines of wobbly, tapering strokes roughly following the line from `from` to `to`
+pub(crate) fn strokes(from: (f64, f64), to: (f64, f64), width: f64, seed: u64) -> Vec<Shape> {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let length = dx.hypot(dy);
+    if length == 0_f64 || width <= 0_f64 {
+        return vec![];
+    }
+    let (ux, uy) = (dx / length, dy / length);
+    let (nx, ny) = (-uy, ux);
+    let steps = ((length / STEP).ceil() as usize).max(4);
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..STROKES)
+        .map(|_| {
+            let start = rng.gen_range(-OVERSHOOT..=OVERSHOOT) * width;
+            let end = length + rng.gen_range(-OVERSHOOT..=OVERSHOOT) * width;
+            let bow = rng.gen_range(-BOW..=BOW) * length;
+            let wiggle = rng.gen_range(-WIGGLE..=WIGGLE) * width;
+            let waves = rng.gen_range(1..=3) as f64;
+            let phase = rng.gen_range(0_f64..TAU);
+            // Strokes overlap, so together they come to about the line's width
+            let thickness = width * rng.gen_range(0.5..0.8);
+
+            let mut left = Vec::with_capacity(2 * steps + 2);
+            let mut right = Vec::with_capacity(steps + 1);
+            for step in 0..=steps {
+                let t = step as f64 / steps as f64;
+                let along = start + (end - start) * t;
+                let across = bow * (PI * t).sin() + wiggle * (TAU * waves * t + phase).sin();
+                let half = thickness * (TAPER + (1_f64 - TAPER) * (PI * t).sin()) / 2_f64;
+                let (x, y) = (
+                    from.0 + ux * along + nx * across,
+                    from.1 + uy * along + ny * across,
+                );
+                left.push((x + nx * half, y + ny * half));
+                right.push((x - nx * half, y - ny * half));
+            }
+            left.extend(right.into_iter().rev());
+            Shape::Area { points: left }
+        })
+        .collect()
+}
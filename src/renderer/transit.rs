@@ -0,0 +1,235 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::drawing::{Drawing, Line, Point};
+use crate::marker::{MarkerShape, STATION_LINES};
+use crate::render::{Colour, Shape};
+
+/// Furthest back along each side a corner is rounded from
+const CORNER_RADIUS: f64 = 50_f64;
+/// Straight edges used to approximate each rounded corner
+const CORNER_SEGMENTS: usize = 8;
+/// Distance between the middles of lines sharing a corridor, in line widths
+const PARALLEL_SPACING: f64 = 1.25;
+/// Width of the outline around an interchange
+const INTERCHANGE_STROKE: f64 = 8_f64;
+/// Runs shorter than this are left out, so lines that are almost diagonal aren't bent
+const TOLERANCE: f64 = 1e-6;
+
+type Position = (f64, f64);
+
+/// The lines ending at a grid point
+#[derive(Default)]
+struct End {
+    count: usize,
+    layers: BTreeSet<usize>,
+    /// How far the widest set of lines ending here spreads either side of the point
+    reach: f64,
+}
+
+impl End {
+    fn is_interchange(&self) -> bool {
+        self.layers.len() > 1 || self.count >= STATION_LINES
+    }
+}
+
+/// Lays out the visible layers as metro map lines, topped with interchanges where lines of
+/// different layers, or a station's worth of lines, end
+pub(crate) fn shapes(drawing: &Drawing) -> Vec<Shape> {
+    let layers = drawing.layers();
+    // The layer of each line shown between the same two points, whichever way it was drawn
+    let mut corridors: BTreeMap<Line, Vec<usize>> = BTreeMap::new();
+    for (idx, layer) in layers.iter().enumerate() {
+        if layer.visible {
+            for (from, to) in layer.lines() {
+                let corridor = (*from.min(to), *from.max(to));
+                corridors.entry(corridor).or_default().push(idx);
+            }
+        }
+    }
+
+    let mut routes: Vec<Vec<Shape>> = vec![Vec::new(); layers.len()];
+    let mut ends: BTreeMap<Point, End> = BTreeMap::new();
+    for ((from, to), corridor) in &corridors {
+        let widest = corridor
+            .iter()
+            .map(|idx| layers[*idx].line_width)
+            .fold(0_f64, f64::max);
+        let spacing = widest * PARALLEL_SPACING;
+        let spread = spacing * (corridor.len() - 1) as f64;
+        for point in [from, to] {
+            let end = ends.entry(*point).or_default();
+            end.count += corridor.len();
+            end.layers.extend(corridor);
+            end.reach = end.reach.max((spread + widest) / 2_f64);
+        }
+
+        let route = route(drawing.position(*from), drawing.position(*to));
+        for (position, idx) in corridor.iter().enumerate() {
+            // Side by side, centred on the route
+            let offset = spacing * position as f64 - spread / 2_f64;
+            routes[*idx].push(Shape::Path {
+                points: round_corners(&offset_route(&route, offset)),
+                width: layers[*idx].line_width,
+            });
+        }
+    }
+
+    let mut shapes = Vec::new();
+    for (idx, mut layer_shapes) in routes.into_iter().enumerate() {
+        let layer = &layers[idx];
+        if !layer.visible {
+            continue;
+        }
+        let marked = ends
+            .iter()
+            .filter(|(_, end)| !end.is_interchange() && end.layers.contains(&idx))
+            .map(|(point, _)| drawing.position(*point))
+            .collect();
+        layer_shapes.extend(drawing.markers.endpoint.shapes(marked));
+        shapes.push(Shape::Group {
+            id: Some(format!("layer-{}", idx + 1)),
+            colour: layer.colour,
+            shapes: layer_shapes,
+        });
+    }
+
+    let interchanges: Vec<(Position, f64)> = ends
+        .iter()
+        .filter(|(_, end)| end.is_interchange())
+        .map(|(point, end)| (drawing.position(*point), end.reach + INTERCHANGE_STROKE))
+        .collect();
+    let station = &drawing.markers.station;
+    if !interchanges.is_empty() && station.shape != MarkerShape::None {
+        // Interchanges span several lines, so rather than following them they're white
+        // outlined in black unless the station marker has colours of its own
+        let mut interchange_shapes: Vec<Shape> = interchanges
+            .iter()
+            .map(|(centre, radius)| Shape::Dot {
+                centre: *centre,
+                radius: *radius,
+            })
+            .collect();
+        interchange_shapes.push(Shape::Group {
+            id: None,
+            colour: station.stroke.unwrap_or(Colour::BLACK),
+            shapes: interchanges
+                .iter()
+                .map(|(centre, radius)| Shape::Ring {
+                    centre: *centre,
+                    radius: radius - INTERCHANGE_STROKE / 2_f64,
+                    width: INTERCHANGE_STROKE,
+                })
+                .collect(),
+        });
+        shapes.push(Shape::Group {
+            id: Some("interchanges".to_string()),
+            colour: station.fill.unwrap_or(Colour::WHITE),
+            shapes: interchange_shapes,
+        });
+    }
+    shapes
+}
+
+/// The routes of the metro map lines, without their widths, colours or interchanges
+pub(crate) fn paths(drawing: &Drawing) -> Vec<Vec<Position>> {
+    fn collect(shapes: Vec<Shape>, paths: &mut Vec<Vec<Position>>) {
+        for shape in shapes {
+            match shape {
+                Shape::Path { points, .. } => paths.push(points),
+                Shape::Group { shapes, .. } => collect(shapes, paths),
+                _ => {}
+            }
+        }
+    }
+    let mut paths = Vec::new();
+    collect(shapes(drawing), &mut paths);
+    paths
+}
+
+/// A route between two positions made of horizontal, vertical & 45° runs. Lines that aren't
+/// already one of those get a diagonal in the middle, so the route is the same either way
+fn route(from: Position, to: Position) -> Vec<Position> {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let diagonal = dx.abs().min(dy.abs());
+    let straight = dx.abs().max(dy.abs()) - diagonal;
+    if diagonal < TOLERANCE || straight < TOLERANCE {
+        return vec![from, to];
+    }
+    let (sx, sy) = if dx.abs() > dy.abs() {
+        (dx.signum() * straight / 2_f64, 0_f64)
+    } else {
+        (0_f64, dy.signum() * straight / 2_f64)
+    };
+    vec![from, (from.0 + sx, from.1 + sy), (to.0 - sx, to.1 - sy), to]
+}
+
+/// Unit vector a quarter turn clockwise from the direction of travel
+fn normal(from: Position, to: Position) -> Position {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let length = dx.hypot(dy);
+    if length == 0_f64 {
+        (0_f64, 0_f64)
+    } else {
+        (-dy / length, dx / length)
+    }
+}
+
+/// Moves a route sideways, keeping each run parallel to the original
+fn offset_route(route: &[Position], offset: f64) -> Vec<Position> {
+    if offset == 0_f64 || route.len() < 2 {
+        return route.to_vec();
+    }
+    let normals: Vec<Position> = route.windows(2).map(|run| normal(run[0], run[1])).collect();
+    route
+        .iter()
+        .enumerate()
+        .map(|(idx, (x, y))| {
+            let before = normals[idx.saturating_sub(1)];
+            let after = normals[idx.min(normals.len() - 1)];
+            // Mitre the corners, which at most turn by 45° so never spike far
+            let scale = offset / (1_f64 + before.0 * after.0 + before.1 * after.1);
+            (
+                x + (before.0 + after.0) * scale,
+                y + (before.1 + after.1) * scale,
+            )
+        })
+        .collect()
+}
+
+/// Moves a distance from one position towards another
+fn towards(from: Position, to: Position, distance: f64) -> Position {
+    let length = (to.0 - from.0).hypot(to.1 - from.1);
+    if length == 0_f64 {
+        return from;
+    }
+    let t = distance / length;
+    (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t)
+}
+
+/// Replaces each corner of a route with a curve
+fn round_corners(route: &[Position]) -> Vec<Position> {
+    if route.len() < 3 {
+        return route.to_vec();
+    }
+    let mut rounded = vec![route[0]];
+    for corner in route.windows(3) {
+        let (before, at, after) = (corner[0], corner[1], corner[2]);
+        // At most half way along each side, leaving room for the next corner
+        let cut = CORNER_RADIUS
+            .min((at.0 - before.0).hypot(at.1 - before.1) / 2_f64)
+            .min((after.0 - at.0).hypot(after.1 - at.1) / 2_f64);
+        let start = towards(at, before, cut);
+        let end = towards(at, after, cut);
+        for step in 0..=CORNER_SEGMENTS {
+            // Quadratic curve from the start to the end, pulled towards the corner
+            let t = step as f64 / CORNER_SEGMENTS as f64;
+            let (a, b, c) = ((1_f64 - t).powi(2), 2_f64 * (1_f64 - t) * t, t.powi(2));
+            rounded.push((
+                a * start.0 + b * at.0 + c * end.0,
+                a * start.1 + b * at.1 + c * end.1,
+            ));
+        }
+    }
+    rounded.push(route[route.len() - 1]);
+    rounded
+}
@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+
+use crate::drawing::{Drawing, Line, Point};
+
+/// Sectors the directions of lines are counted in, covering half a turn as lines go both ways
+pub const DIRECTIONS: usize = 8;
+
+/// Figures describing the visible lines of a drawing, for comparing drawings
+#[derive(Clone, PartialEq, Debug)]
+pub struct Stats {
+    pub lines: usize,
+    pub rows: u16,
+    /// Lengths are in cells, taken as squares of the same area when they're stretched, so
+    /// drawings on different canvases compare
+    pub total_length: f64,
+    pub average_length: f64,
+    /// Lines by length, the first bucket being up to 1 cell long, the next up to 2 & so on
+    pub lengths: Vec<usize>,
+    /// Lines by direction on the canvas, each sector spanning an equal angle clockwise from
+    /// horizontal
+    pub directions: [usize; DIRECTIONS],
+    /// How many points have each number of line ends at them
+    pub degrees: BTreeMap<usize, usize>,
+    /// Pairs of lines crossing away from their ends
+    pub crossings: usize,
+    /// Sets of lines joined end to end
+    pub components: usize,
+    /// Grid & custom points without any lines ending at them
+    pub unused_points: usize,
+}
+
+impl Stats {
+    pub fn new(drawing: &Drawing) -> Stats {
+        let lines: Vec<Line> = drawing.visible_lines().copied().collect();
+        let cell = match (drawing.x_spacing(), drawing.y_spacing()) {
+            (width, height) if width > 0_f64 && height > 0_f64 => (width * height).sqrt(),
+            // A single row or column only has spacing the other way
+            (width, height) => width.max(height),
+        };
+        let offset = |(from, to): &Line| {
+            let ((x1, y1), (x2, y2)) = (drawing.position(*from), drawing.position(*to));
+            (x2 - x1, y2 - y1)
+        };
+        let length = |line: &Line| {
+            let (dx, dy) = offset(line);
+            if cell > 0_f64 {
+                dx.hypot(dy) / cell
+            } else {
+                0_f64
+            }
+        };
+
+        let total_length: f64 = lines.iter().map(length).sum();
+        let mut lengths = Vec::new();
+        let mut directions = [0; DIRECTIONS];
+        for line @ (from, to) in &lines {
+            let bucket = (length(line).ceil() as usize).max(1) - 1;
+            if lengths.len() <= bucket {
+                lengths.resize(bucket + 1, 0);
+            }
+            lengths[bucket] += 1;
+
+            if from != to {
+                let (dx, dy) = offset(line);
+                let angle = dy.atan2(dx).rem_euclid(PI);
+                let sector = (angle / PI * DIRECTIONS as f64) as usize;
+                directions[sector.min(DIRECTIONS - 1)] += 1;
+            }
+        }
+
+        let mut ends: BTreeMap<Point, usize> = BTreeMap::new();
+        for (from, to) in &lines {
+            *ends.entry(*from).or_default() += 1;
+            *ends.entry(*to).or_default() += 1;
+        }
+        let mut degrees = BTreeMap::new();
+        for degree in ends.values() {
+            *degrees.entry(*degree).or_default() += 1;
+        }
+
+        let crossings = lines
+            .iter()
+            .enumerate()
+            .map(|(idx, a)| lines[idx + 1..].iter().filter(|b| crosses(a, b)).count())
+            .sum();
+
+        Stats {
+            lines: lines.len(),
+            rows: drawing.grid_height,
+            total_length,
+            average_length: if lines.is_empty() {
+                0_f64
+            } else {
+                total_length / lines.len() as f64
+            },
+            lengths,
+            directions,
+            degrees,
+            crossings,
+            components: components(&lines),
+            unused_points: drawing
+                .marked_points()
+                .filter(|point| !ends.contains_key(point))
+                .count(),
+        }
+    }
+}
+
+/// Which side of the line through `a` & `b` that `c` is on, 0 if it's on the line
+fn orientation(a: Point, b: Point, c: Point) -> i64 {
+    let cross = (b.0 as i64 - a.0 as i64) * (c.1 as i64 - a.1 as i64)
+        - (b.1 as i64 - a.1 as i64) * (c.0 as i64 - a.0 as i64);
+    cross.signum()
+}
+
+/// Whether two lines cross each other, not counting touching or overlapping
+fn crosses((a1, a2): &Line, (b1, b2): &Line) -> bool {
+    let (o1, o2) = (orientation(*a1, *a2, *b1), orientation(*a1, *a2, *b2));
+    let (o3, o4) = (orientation(*b1, *b2, *a1), orientation(*b1, *b2, *a2));
+    o1 * o2 < 0 && o3 * o4 < 0
+}
+
+/// Number of sets of lines that are joined end to end
+fn components(lines: &[Line]) -> usize {
+    // Union-find over the ends of the lines
+    let mut parents: BTreeMap<Point, Point> = BTreeMap::new();
+    fn root(parents: &mut BTreeMap<Point, Point>, point: Point) -> Point {
+        let parent = *parents.entry(point).or_insert(point);
+        if parent == point {
+            return point;
+        }
+        let root = root(parents, parent);
+        parents.insert(point, root);
+        root
+    }
+    for (from, to) in lines {
+        let (from, to) = (root(&mut parents, *from), root(&mut parents, *to));
+        parents.insert(from, to);
+    }
+    let points: Vec<Point> = parents.keys().copied().collect();
+    points
+        .into_iter()
+        .filter(|point| root(&mut parents, *point) == *point)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(width: u16, height: u16, lines: &[Line]) -> Stats {
+        let mut drawing = Drawing::new();
+        drawing.set_grid_width(width).unwrap();
+        drawing.set_grid_height(height).unwrap();
+        drawing.add_lines(lines).unwrap();
+        Stats::new(&drawing)
+    }
+
+    #[test]
+    fn counts_crossing_x() {
+        let stats = stats(3, 3, &[((0, 0), (120, 120)), ((120, 0), (0, 120))]);
+        assert_eq!(stats.crossings, 1);
+        assert_eq!(stats.components, 2);
+        assert_eq!(stats.degrees, BTreeMap::from([(1, 4)]));
+    }
+
+    #[test]
+    fn t_junction_isnt_a_crossing() {
+        let stats = stats(3, 3, &[((0, 0), (120, 0)), ((60, 0), (60, 120))]);
+        assert_eq!(stats.crossings, 0);
+    }
+
+    #[test]
+    fn collinear_overlap_isnt_a_crossing() {
+        let stats = stats(4, 2, &[((0, 0), (120, 0)), ((60, 0), (180, 0))]);
+        assert_eq!(stats.crossings, 0);
+    }
+
+    #[test]
+    fn counts_separate_components() {
+        let stats = stats(
+            4,
+            3,
+            &[
+                ((0, 0), (60, 0)),
+                ((60, 0), (60, 60)),
+                ((120, 120), (180, 120)),
+            ],
+        );
+        assert_eq!(stats.components, 2);
+        assert_eq!(stats.degrees, BTreeMap::from([(1, 4), (2, 1)]));
+    }
+}
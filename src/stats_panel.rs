@@ -0,0 +1,112 @@
+use std::f64::consts::PI;
+
+use seed::{prelude::*, *};
+use track_drawer::drawing::Drawing;
+use track_drawer::stats::{Stats, DIRECTIONS};
+
+/// Figures & charts describing a drawing, shown in the sidebars
+pub fn view<Msg>(drawing: &Drawing) -> Node<Msg> {
+    let stats = Stats::new(drawing);
+    div![
+        C!["flex flex-col w-full text-sm text-gray-600"],
+        dl![
+            C!["grid grid-cols-2 gap-1"],
+            dt!["Lines"],
+            dd![stats.lines],
+            dt!["Rows"],
+            dd![stats.rows.to_string()],
+            dt!["Total length"],
+            dd![format!("{:.1}", stats.total_length)],
+            dt!["Average length"],
+            dd![format!("{:.2}", stats.average_length)],
+            dt!["Crossings"],
+            dd![stats.crossings],
+            dt!["Connected parts"],
+            dd![stats.components],
+            dt!["Unused points"],
+            dd![stats.unused_points],
+        ],
+        IF!(stats.lines > 0 => vec![
+            label![C!["mt-2 mb-1"], "Lengths (cells):"],
+            bar_chart(
+                stats
+                    .lengths
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, count)| (format!("≤{}", idx + 1), *count))
+                    .collect()
+            ),
+            label![C!["mt-2 mb-1"], "Lines at each point:"],
+            bar_chart(
+                stats
+                    .degrees
+                    .iter()
+                    .map(|(degree, count)| (degree.to_string(), *count))
+                    .collect()
+            ),
+            label![C!["mt-2 mb-1"], "Directions:"],
+            rose_view(&stats),
+        ])
+    ]
+}
+
+/// Vertical bars scaled to the largest count, each labelled underneath
+fn bar_chart<Msg>(bars: Vec<(String, usize)>) -> Node<Msg> {
+    let max = bars
+        .iter()
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    div![
+        C!["flex flex-row items-end h-20 space-x-px"],
+        bars.into_iter().map(|(label, count)| div![
+            C!["flex flex-col flex-1 items-center justify-end h-full"],
+            attrs! {At::Title => format!("{}: {}", label, count)},
+            div![
+                C!["w-full bg-blue-400 rounded-t"],
+                style! {St::Height => format!("{}%", 100 * count / max)}
+            ],
+            span![C!["text-xs"], label]
+        ])
+    ]
+}
+
+/// A rose diagram, with a wedge each way for the lines in each direction
+fn rose_view<Msg>(stats: &Stats) -> Node<Msg> {
+    let max = stats.directions.iter().copied().max().unwrap_or(0).max(1);
+    let sector = PI / DIRECTIONS as f64;
+    let wedge = |start: f64, radius: f64| {
+        let end = start + sector;
+        format!(
+            "M0 0L{} {}A{r} {r} 0 0 1 {} {}Z",
+            radius * start.cos(),
+            radius * start.sin(),
+            radius * end.cos(),
+            radius * end.sin(),
+            r = radius
+        )
+    };
+    svg![
+        C!["w-32 h-32 self-center"],
+        attrs! {At::ViewBox => "-1 -1 2 2"},
+        circle![attrs! {
+            At::R => 1,
+            At::Fill => "none",
+            At::Stroke => "#cbd5e0",
+            At::StrokeWidth => 0.02
+        }],
+        stats
+            .directions
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .flat_map(|(idx, count)| {
+                let radius = *count as f64 / max as f64;
+                let start = idx as f64 * sector;
+                // Lines go both ways, so each direction gets a wedge opposite too
+                vec![wedge(start, radius), wedge(start + PI, radius)]
+            })
+            .map(|d| path![attrs! {At::D => d, At::Fill => "#63b3ed"}])
+    ]
+}
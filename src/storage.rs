@@ -1 +1,46 @@
+use seed::prelude::*;
+use std::collections::HashSet;
+use track_drawer::drawing::Drawing;
+
 pub const STORAGE_KEY: &str = "DRAWS";
+pub const KEYMAP_KEY: &str = "KEYMAP";
+pub const EXPORT_KEY: &str = "EXPORT";
+pub const EXPLORE_KEY: &str = "EXPLORE";
+/// Session storage key for a drawing handed from the gallery to the draw page
+pub const REMIX_KEY: &str = "REMIX";
+/// Session storage key for the drawings picked in the gallery to breed from
+pub const BREED_KEY: &str = "BREED";
+
+/// Loads the saved drawings, naming any that were saved before drawings had names
+pub fn load_drawings() -> Vec<Drawing> {
+    let mut drawings: Vec<Drawing> = LocalStorage::get(STORAGE_KEY).unwrap_or_default();
+    if drawings.iter().any(|drawing| drawing.title.is_empty()) {
+        let used: HashSet<String> = drawings
+            .iter()
+            .map(|drawing| drawing.title.clone())
+            .collect();
+        let mut titles = (1..)
+            .map(|n| format!("Track {}", n))
+            .filter(|title| !used.contains(title));
+        for drawing in drawings
+            .iter_mut()
+            .filter(|drawing| drawing.title.is_empty())
+        {
+            drawing.title = titles.next().unwrap();
+        }
+        save_drawings(&drawings);
+    }
+    drawings
+}
+
+pub fn save_drawings(drawings: &[Drawing]) {
+    LocalStorage::insert(STORAGE_KEY, &drawings).expect("Saving drawings failed")
+}
+
+/// The first of "Track 1", "Track 2"... that no saved drawing is already called
+pub fn default_title(drawings: &[Drawing]) -> String {
+    (1..)
+        .map(|n| format!("Track {}", n))
+        .find(|title| !drawings.iter().any(|drawing| drawing.title == *title))
+        .unwrap()
+}
@@ -0,0 +1,243 @@
+use serde::{Deserialize, Serialize};
+
+use crate::drawing::{Drawing, DrawingError, Line, Point};
+
+/// Reflection across the vertical centre line of the grid
+fn mirror_x(drawing: &Drawing, (x, y): Point) -> Point {
+    (drawing.extent().0 - x, y)
+}
+
+/// Reflection across the horizontal centre line of the grid
+fn mirror_y(drawing: &Drawing, (x, y): Point) -> Point {
+    (x, drawing.extent().1 - y)
+}
+
+fn rotate_180(drawing: &Drawing, point: Point) -> Point {
+    mirror_x(drawing, mirror_y(drawing, point))
+}
+
+/// Quarter turn clockwise, only staying within the grid when it's square
+fn rotate_90(drawing: &Drawing, (x, y): Point) -> Point {
+    (drawing.extent().1 - y, x)
+}
+
+/// Lines that are the same, whichever way round they're drawn
+fn same_line(a: &Line, b: &Line) -> bool {
+    a == b || (a.0 == b.1 && a.1 == b.0)
+}
+
+/// Which partners each line drawn gets, so the drawing stays symmetric
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum Symmetry {
+    #[default]
+    None,
+    /// Mirrored left to right
+    Horizontal,
+    /// Mirrored top to bottom
+    Vertical,
+    Both,
+    /// Turned half way around the centre
+    Rotational2,
+    /// Turned by each quarter around the centre, only on square grids
+    Rotational4,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 6] = [
+        Symmetry::None,
+        Symmetry::Horizontal,
+        Symmetry::Vertical,
+        Symmetry::Both,
+        Symmetry::Rotational2,
+        Symmetry::Rotational4,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Symmetry::None => "None",
+            Symmetry::Horizontal => "Mirror left & right",
+            Symmetry::Vertical => "Mirror top & bottom",
+            Symmetry::Both => "Mirror both ways",
+            Symmetry::Rotational2 => "2-fold rotation",
+            Symmetry::Rotational4 => "4-fold rotation",
+        }
+    }
+
+    /// Whether the symmetry works as described on the drawing's grid
+    pub fn fits(&self, drawing: &Drawing) -> bool {
+        *self != Symmetry::Rotational4 || drawing.grid_width == drawing.grid_height
+    }
+
+    /// Where a point & its partners are, in the same order for every point, with no partners
+    /// when the symmetry doesn't fit the grid
+    fn images(&self, drawing: &Drawing, point: Point) -> Vec<Point> {
+        match self {
+            Symmetry::None => vec![point],
+            Symmetry::Horizontal => vec![point, mirror_x(drawing, point)],
+            Symmetry::Vertical => vec![point, mirror_y(drawing, point)],
+            Symmetry::Both => vec![
+                point,
+                mirror_x(drawing, point),
+                mirror_y(drawing, point),
+                rotate_180(drawing, point),
+            ],
+            Symmetry::Rotational2 => vec![point, rotate_180(drawing, point)],
+            Symmetry::Rotational4 if self.fits(drawing) => {
+                std::iter::successors(Some(point), |point| Some(rotate_90(drawing, *point)))
+                    .take(4)
+                    .collect()
+            }
+            Symmetry::Rotational4 => vec![point],
+        }
+    }
+
+    /// The point followed by its distinct partners, all within the drawing's grid
+    pub fn points(&self, drawing: &Drawing, point: Point) -> Vec<Point> {
+        let mut points: Vec<Point> = Vec::with_capacity(4);
+        for image in self.images(drawing, point) {
+            if !points.contains(&image) {
+                points.push(image);
+            }
+        }
+        points
+    }
+
+    /// The line followed by its distinct partners, all within the drawing's grid, or an error
+    /// if the symmetry doesn't fit the grid
+    pub fn lines(&self, drawing: &Drawing, (from, to): Line) -> Result<Vec<Line>, DrawingError> {
+        if !self.fits(drawing) {
+            return Err(DrawingError::NotSquare);
+        }
+        let images = self
+            .images(drawing, from)
+            .into_iter()
+            .zip(self.images(drawing, to));
+        let mut lines: Vec<Line> = Vec::with_capacity(4);
+        for image in images {
+            if !lines.iter().any(|line| same_line(line, &image)) {
+                lines.push(image);
+            }
+        }
+        Ok(lines)
+    }
+}
+
+/// A change made to every line of a drawing at once
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Transform {
+    FlipHorizontal,
+    FlipVertical,
+    Rotate180,
+    /// Swaps columns with rows, along with the grid's width & height
+    Transpose,
+}
+
+impl Transform {
+    pub const ALL: [Transform; 4] = [
+        Transform::FlipHorizontal,
+        Transform::FlipVertical,
+        Transform::Rotate180,
+        Transform::Transpose,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Transform::FlipHorizontal => "Flip left & right",
+            Transform::FlipVertical => "Flip top & bottom",
+            Transform::Rotate180 => "Rotate 180°",
+            Transform::Transpose => "Transpose",
+        }
+    }
+
+    /// Moves the lines & custom points, unless a locked layer has lines that would move
+    pub fn apply(&self, drawing: &mut Drawing) -> Result<(), DrawingError> {
+        let mut moved = drawing.clone();
+        if *self == Transform::Transpose {
+            std::mem::swap(&mut moved.grid_width, &mut moved.grid_height);
+        }
+        moved.map_points(|point| match self {
+            Transform::FlipHorizontal => mirror_x(drawing, point),
+            Transform::FlipVertical => mirror_y(drawing, point),
+            Transform::Rotate180 => rotate_180(drawing, point),
+            Transform::Transpose => (point.1, point.0),
+        })?;
+        *drawing = moved;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drawing(width: u16, height: u16) -> Drawing {
+        let mut drawing = Drawing::new();
+        drawing.set_grid_width(width).unwrap();
+        drawing.set_grid_height(height).unwrap();
+        drawing
+    }
+
+    #[test]
+    fn partners_are_distinct_whichever_way_round() {
+        let drawing = drawing(5, 3);
+        for line in [((0, 60), (240, 60)), ((240, 60), (0, 60))] {
+            assert_eq!(Symmetry::Horizontal.lines(&drawing, line), Ok(vec![line]));
+        }
+        let centre = ((120, 120), (120, 0));
+        assert_eq!(Symmetry::Both.lines(&drawing, centre), Ok(vec![centre]));
+        assert_eq!(
+            Symmetry::Both.lines(&drawing, ((0, 0), (60, 60))),
+            Ok(vec![
+                ((0, 0), (60, 60)),
+                ((240, 0), (180, 60)),
+                ((0, 120), (60, 60)),
+                ((240, 120), (180, 60)),
+            ])
+        );
+        assert_eq!(Symmetry::Both.points(&drawing, (120, 60)), vec![(120, 60)]);
+    }
+
+    #[test]
+    fn rotational4_needs_a_square_grid() {
+        let line = ((0, 0), (60, 0));
+        assert_eq!(
+            Symmetry::Rotational4.lines(&drawing(5, 3), line),
+            Err(DrawingError::NotSquare)
+        );
+        assert_eq!(
+            Symmetry::Rotational4.lines(&drawing(3, 3), line),
+            Ok(vec![
+                line,
+                ((120, 0), (120, 60)),
+                ((120, 120), (60, 120)),
+                ((0, 120), (0, 60)),
+            ])
+        );
+    }
+
+    #[test]
+    fn transpose_swaps_the_grid() {
+        let mut drawing = drawing(5, 3);
+        drawing.add_line((0, 0), (240, 60)).unwrap();
+        Transform::Transpose.apply(&mut drawing).unwrap();
+        assert_eq!((drawing.grid_width, drawing.grid_height), (3, 5));
+        assert_eq!(drawing.lines(), &[((0, 0), (60, 240))]);
+    }
+
+    #[test]
+    fn transform_leaves_locked_layers_alone() {
+        let mut drawing = drawing(5, 3);
+        drawing.add_line((0, 0), (60, 0)).unwrap();
+        drawing.add_custom_point((60, 60)).unwrap();
+        drawing.layer_mut(0).unwrap().locked = true;
+        drawing.add_layer();
+        drawing.add_line((0, 60), (60, 60)).unwrap();
+        assert_eq!(
+            Transform::FlipHorizontal.apply(&mut drawing),
+            Err(DrawingError::Locked)
+        );
+        assert_eq!(drawing.layers()[0].lines(), &[((0, 0), (60, 0))]);
+        assert_eq!(drawing.layers()[1].lines(), &[((0, 60), (60, 60))]);
+        assert_eq!(drawing.custom_points(), &[(60, 60)]);
+    }
+}
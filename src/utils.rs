@@ -1,29 +1,45 @@
-use crate::js_sys::Array;
-use seed::prelude::{ElRef, JsValue};
-use web_sys::{Blob, BlobPropertyBag, XmlSerializer};
+use crate::js_sys::{encode_uri_component, Array, Date, Uint8Array};
+use web_sys::{Blob, BlobPropertyBag};
 
-/// Grabs the content on an SVG node & opens a download dialog
-pub fn download_svg(el_ref: &ElRef<web_sys::HtmlElement>) {
-    let svg_buf = XmlSerializer::new()
-        .unwrap()
-        .serialize_to_string(&el_ref.shared_node_ws.clone_inner().unwrap())
-        .unwrap();
+/// Opens a download dialog for some binary content
+pub fn download_bytes(bytes: &[u8], mime_type: &str, filename: &str) {
+    let arr = Array::new_with_length(1);
+    arr.set(0, Uint8Array::from(bytes).into());
 
-    let mut blob_type = BlobPropertyBag::new();
-    blob_type.type_("image/svg+xml;charset=utf-8");
+    download_blob(&arr, mime_type, filename);
+}
 
-    let arr = Array::new_with_length(1);
-    arr.set(0, JsValue::from_str(&svg_buf));
+/// Embeds an SVG document in a URL, so it can be shown with an `img` rather than as DOM nodes
+pub fn svg_data_url(svg: &str) -> String {
+    format!(
+        "data:image/svg+xml;charset=utf-8,{}",
+        String::from(encode_uri_component(svg))
+    )
+}
 
-    let blob = Blob::new_with_str_sequence_and_options(&JsValue::from(arr), &blob_type).unwrap();
+fn download_blob(parts: &Array, mime_type: &str, filename: &str) {
+    let mut blob_type = BlobPropertyBag::new();
+    blob_type.type_(mime_type);
+
+    let blob = Blob::new_with_u8_array_sequence_and_options(parts, &blob_type).unwrap();
     let url = web_sys::Url::create_object_url_with_blob(&blob).unwrap();
     let document = web_sys::window().unwrap().document().unwrap();
     let elem = document.create_element("a").unwrap();
     elem.set_attribute("href", &url).unwrap();
-    elem.set_attribute("download", "Track Image").unwrap();
+    elem.set_attribute("download", filename).unwrap();
     let event = document.create_event("MouseEvents").unwrap();
     event.init_event("click");
     document.body().unwrap().append_with_node_1(&elem).unwrap();
     elem.dispatch_event(&event).unwrap();
     document.body().unwrap().remove_child(&elem).unwrap();
 }
+
+/// The current time as an ISO 8601 timestamp in UTC
+pub fn now() -> String {
+    String::from(Date::new_0().to_iso_string())
+}
+
+/// Today's date as `YYYY-MM-DD`
+pub fn today() -> String {
+    now().chars().take(10).collect()
+}